@@ -0,0 +1,248 @@
+//! # err-wrap
+//! The Error-Wrapper pattern from `pat_error-wrap.rs`, without copy-pasting it into every script.
+//!
+//! [`err_kind!`] takes a script's own error enum and generates, next to it:
+//! - built-in variants: `TracingSubscriber`, `OtherErrorDyn`, `OtherErrorString`
+//! - `ErrWrapper { source, spantrace, backtrace }`, whose `From<T: Into<ErrKind>>` captures
+//!   the active tracing spans and a backtrace on every `?`
+//! - `OurResult<A>`, the `ToOther` trait (`.to_other()` on anything boxable as a dyn error),
+//!   and `ErrKind::make_dyn_error`
+//!
+//! The enum derives its `Display`, `From` & `Error` from **derive_more** (the built-in variants carry its attributes),
+//! so scripts need it too.
+//!
+//! ## Traces
+//! - spantrace: needs a subscriber with `tracing_error::ErrorLayer`; [`tracing_subscriber_shortened_boilerplate`] installs one.
+//!   Spans are thread-local: only those entered on the erroring thread show up.
+//! - backtrace: `std`'s, so only captured with `RUST_BACKTRACE=1` (or `RUST_LIB_BACKTRACE=1`, errors only).
+//!
+//! ## Use (from a script's frontmatter)
+//! ```toml
+//! [dependencies]
+//! derive_more = { version = "1.0", features = ["display", "error", "from"] }
+//! err-wrap = { path = "../crate-libs/err-wrap" }
+//! ```
+//! ```rust,ignore
+//! use derive_more::{Display, Error, From};
+//!
+//! err_wrap::err_kind! {
+//!         #[derive(Debug, Display, From, Error)]
+//!         pub enum ErrKind {
+//!                 #[display("io error: {}", source)]
+//!                 Io { source: std::io::Error },
+//!         }
+//! }
+//!
+//! fn main() -> OurResult<()> {
+//!         err_wrap::tracing_subscriber_shortened_boilerplate()?;
+//!         std::fs::read("missing")?;
+//!         Ok(())
+//! }
+//! ```
+use std::{backtrace::Backtrace, fmt};
+
+use tracing_error::SpanTrace;
+use tracing_subscriber::{filter::LevelFilter, prelude::*};
+
+/// Declare a script's `ErrKind` and generate its `ErrWrapper`, `OurResult` and `ToOther` (see the crate docs).
+///
+/// Variants are written as for any derive_more enum: `#[from(ignore)]` on those that
+/// can't be unambiguously built from their field (e.g. most custom errors).
+#[macro_export]
+macro_rules! err_kind {
+        (
+                $(#[$meta:meta])*
+                $vis:vis enum $kind:ident { $($variants:tt)* }
+        ) => {
+                /// "Core" error enum: the kinds (and sources) of errors this script expects.
+                $(#[$meta])*
+                $vis enum $kind {
+                        #[display("Error setting tracing subscriber default: {}", source)]
+                        TracingSubscriber { source: $crate::__private::SetGlobalDefaultError },
+
+                        // `other` errors: no need to add a variant for every new error source while exploring
+                        #[from(ignore)] // use `make_dyn_error` or `.to_other()`; would conflict with the other `From`s
+                        #[display("Uncategorized Error (dyn error object): {}", source)]
+                        OtherErrorDyn { source: Box<dyn std::error::Error + Send + Sync> },
+
+                        #[display(r#"Uncategorized string err: "{}""#, source_string)]
+                        OtherErrorString { source_string: String },
+
+                        $($variants)*
+                }
+                impl $kind {
+                        /// Convenience for creating `OtherErrorDyn`.
+                        $vis fn make_dyn_error<E>(error: E) -> Self
+                        where
+                                E: Into<Box<dyn std::error::Error + Send + Sync>>,
+                        {
+                                Self::OtherErrorDyn { source: error.into() }
+                        }
+                }
+
+                /// The error kind, plus whatever was going on when it happened.
+                $vis struct ErrWrapper {
+                        $vis source:    $kind,
+                        $vis spantrace: $crate::__private::SpanTrace,
+                        $vis backtrace: std::backtrace::Backtrace,
+                }
+                impl std::fmt::Display for ErrWrapper {
+                        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                                $crate::__private::fmt_wrapped(f, &self.source, &self.spantrace, &self.backtrace)
+                        }
+                }
+                // Display as Debug, so `main`'s returned error gets the pretty-printed traces.
+                impl std::fmt::Debug for ErrWrapper {
+                        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                                std::fmt::Display::fmt(self, f)
+                        }
+                }
+                impl std::error::Error for ErrWrapper {
+                        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                                Some(&self.source)
+                        }
+                }
+                // What makes the pattern practical: every `?` on something `Into<ErrKind>` fills in the traces.
+                impl<T> From<T> for ErrWrapper
+                where
+                        T: Into<$kind>,
+                {
+                        fn from(error: T) -> Self {
+                                Self {
+                                        source:    error.into(),
+                                        spantrace: $crate::__private::SpanTrace::capture(),
+                                        backtrace: std::backtrace::Backtrace::capture(),
+                                }
+                        }
+                }
+
+                $vis type OurResult<A> = std::result::Result<A, ErrWrapper>;
+
+                /// Wrap any dyn-boxable error as `OtherErrorDyn`.
+                $vis trait ToOther {
+                        fn to_other(self) -> ErrWrapper;
+                }
+                impl<E> ToOther for E
+                where
+                        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+                {
+                        fn to_other(self) -> ErrWrapper {
+                                $kind::make_dyn_error(self).into()
+                        }
+                }
+        };
+}
+
+/// Shortened version of an actual tracing_subscriber boilerplate function.
+/// Everything to stdout, and spans recorded for `SpanTrace`s.
+pub fn tracing_subscriber_shortened_boilerplate() -> Result<(), tracing::subscriber::SetGlobalDefaultError> {
+        const OUTPUT_LOGGING_LEVEL: LevelFilter = LevelFilter::TRACE;
+        const ERROR_LOGGING_LEVEL: LevelFilter = LevelFilter::TRACE;
+
+        let error_layer = tracing_error::ErrorLayer::default().with_filter(ERROR_LOGGING_LEVEL);
+        let fmt_layer = tracing_subscriber::fmt::Layer::default().with_filter(OUTPUT_LOGGING_LEVEL);
+
+        let subscriber = tracing_subscriber::Registry::default().with(error_layer).with(fmt_layer);
+
+        tracing::subscriber::set_global_default(subscriber)
+}
+
+/// Used by [`err_kind!`]'s expansion; not part of the API.
+#[doc(hidden)]
+pub mod __private {
+        pub use tracing::subscriber::SetGlobalDefaultError;
+        pub use tracing_error::SpanTrace;
+
+        pub use super::fmt_wrapped;
+}
+
+#[doc(hidden)]
+pub fn fmt_wrapped(f: &mut fmt::Formatter<'_>, source: &dyn fmt::Display, spantrace: &SpanTrace, backtrace: &Backtrace) -> fmt::Result {
+        write!(
+                f,
+                "error: {:#}\n\n\nspantrace capture: {:?}\n\n\nspantrace: {:#}\n\n\nbacktrace: {:#}",
+                source,
+                spantrace.status(),
+                spantrace,
+                backtrace,
+        )
+}
+
+#[cfg(test)]
+mod tests {
+        use std::{backtrace::BacktraceStatus, sync::Once, time::Duration};
+
+        use derive_more::{Display, Error, From};
+        use tracing::instrument;
+        use tracing_error::{ErrorLayer, SpanTraceStatus};
+        use tracing_subscriber::prelude::*;
+
+        crate::err_kind! {
+                #[derive(Debug, Display, From, Error)]
+                pub enum ErrKind {
+                        #[from(ignore)]
+                        #[display("superstitious about {:?}", source_dur)]
+                        SuperstitiousConcern { source_dur: Duration },
+
+                        #[display("parse error: {}", source)]
+                        ParseInt { source: std::num::ParseIntError }
+                }
+        }
+
+        /// Run `f` with an `ErrorLayer` subscriber (this thread only) and backtraces on.
+        fn traced<R>(f: impl FnOnce() -> R) -> R {
+                static BACKTRACES_ON: Once = Once::new();
+                // SAFETY: `std` reads (and caches) this on the first capture, which every test only does after this.
+                BACKTRACES_ON.call_once(|| unsafe { std::env::set_var("RUST_LIB_BACKTRACE", "1") });
+                let subscriber = tracing_subscriber::registry().with(ErrorLayer::default());
+                tracing::subscriber::with_default(subscriber, f)
+        }
+
+        #[instrument]
+        fn outer_span(s: &str) -> OurResult<u64> {
+                inner_parse(format!("{s}{s}"))
+        }
+        #[instrument]
+        fn inner_parse(doubled: String) -> OurResult<u64> {
+                Ok(doubled.parse()?)
+        }
+
+        #[test]
+        fn traces_captured_through_question_mark() {
+                let err = traced(|| outer_span("six")).unwrap_err();
+                assert!(matches!(err.source, ErrKind::ParseInt { .. }));
+
+                assert_eq!(err.spantrace.status(), SpanTraceStatus::CAPTURED);
+                let spantrace = err.spantrace.to_string();
+                // innermost span first, with its fields
+                let (inner, outer) = (spantrace.find("inner_parse").unwrap(), spantrace.find("outer_span").unwrap());
+                assert!(inner < outer);
+                assert!(spantrace.contains(r#"doubled="sixsix""#), "{spantrace}");
+
+                assert_eq!(err.backtrace.status(), BacktraceStatus::Captured);
+                assert!(err.backtrace.to_string().contains("inner_parse"));
+
+                let shown = format!("{err:?}");
+                assert!(shown.starts_with("error: parse error: invalid digit"));
+                assert!(shown.contains("spantrace capture: SpanTraceStatus(Captured)"), "{shown}");
+        }
+
+        #[test]
+        fn custom_and_other_kinds() {
+                traced(|| {
+                        let custom = || -> OurResult<()> { Err(ErrKind::SuperstitiousConcern { source_dur: Duration::from_micros(7) })? };
+                        let err = custom().unwrap_err();
+                        assert_eq!(err.source.to_string(), "superstitious about 7µs");
+                        assert_eq!(err.backtrace.status(), BacktraceStatus::Captured);
+
+                        let err = std::fmt::Error.to_other();
+                        assert!(matches!(err.source, ErrKind::OtherErrorDyn { .. }));
+                        let err: ErrWrapper = String::from("oops").into();
+                        assert_eq!(err.source.to_string(), r#"Uncategorized string err: "oops""#);
+                        assert!(std::error::Error::source(&err).is_some());
+                });
+                // no `ErrorLayer`: still an error, just without spans
+                let err: ErrWrapper = ErrKind::make_dyn_error("untraced").into();
+                assert_ne!(err.spantrace.status(), SpanTraceStatus::CAPTURED);
+        }
+}
@@ -0,0 +1,217 @@
+//! # numeric-bounds
+//! Human-friendly integers (and `a..=b` ranges) for cargo-script CLIs.
+//! clap only takes plain digits for integer args; these parsers also take:
+//!
+//! | form            | e.g.                  |
+//! |-----------------|-----------------------|
+//! | `_` separators  | `100_000_000`         |
+//! | scientific      | `1e9`, `1.5E6`        |
+//! | powers          | `2^32`, `10^12`       |
+//! | `k/M/G/T`       | `10k`, `2.5M`, `1G`   |
+//! | ranges          | `1e9..=2e9`, `..=10k` |
+//!
+//! Suffixes are decimal (`k` = 10^3, ... `T` = 10^12).
+//! Decimals are fine as long as the result is whole.  Out-of-range values are errors, never wrapped or clamped.
+//!
+//! ## Use (from a script's frontmatter)
+//! ```toml
+//! [dependencies]
+//! numeric-bounds = { path = "../crate-libs/numeric-bounds" }
+//! ```
+//! ```rust,ignore
+//! #[arg(value_parser = numeric_bounds::parse::<usize>)]
+//! primes_until: Option<usize>,
+//! ```
+use std::any::type_name;
+
+use derive_more::{Display, Error};
+
+/// Why some text isn't a usable number.
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+pub enum BoundsError {
+        #[display("no number given")]
+        Empty,
+        #[display("{input:?} is not a number (try e.g. `1_000_000`, `1e6`, `2^20`, `10k`, or `a..=b`)")]
+        Invalid { input: String },
+        #[display("{input:?} is negative; only non-negative bounds make sense here")]
+        Negative { input: String },
+        #[display("{input:?} is not a whole number")]
+        NotWhole { input: String },
+        #[display("{input:?} is too large to fit in a {target}")]
+        Overflow { input: String, target: &'static str },
+        #[display("range {input:?} is empty or reversed")]
+        Reversed { input: String },
+}
+
+/// Inclusive bounds, as parsed from `max`, `min..=max`, `min..max` or `..=max`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds<T> {
+        pub min: Option<T>,
+        pub max: T,
+}
+
+/// Parse a single human-friendly non-negative integer into `T`.
+pub fn parse<T: TryFrom<u128>>(input: &str) -> Result<T, BoundsError> {
+        let n = parse_u128(input)?;
+        T::try_from(n).map_err(|_| BoundsError::Overflow {
+                input:  input.trim().to_string(),
+                target: type_name::<T>(),
+        })
+}
+
+/// Parse either a single number (`max`) or a range: `min..=max`, `min..max` (exclusive), `..=max`.
+pub fn parse_bounds<T: TryFrom<u128>>(input: &str) -> Result<Bounds<T>, BoundsError> {
+        let reversed = || BoundsError::Reversed { input: input.trim().to_string() };
+        let Some((min, max)) = input.split_once("..") else {
+                return Ok(Bounds { min: None, max: parse(input)? });
+        };
+        let min = match min.trim() {
+                | "" => None,
+                | min => Some(parse_u128(min)?),
+        };
+        let max = match max.strip_prefix('=') {
+                | Some(max) => parse_u128(max)?,
+                | None => parse_u128(max)?.checked_sub(1).ok_or_else(reversed)?,
+        };
+        if min.is_some_and(|min| min > max) {
+                return Err(reversed());
+        }
+        let fit = |n: u128| {
+                T::try_from(n).map_err(|_| BoundsError::Overflow {
+                        input:  input.trim().to_string(),
+                        target: type_name::<T>(),
+                })
+        };
+        Ok(Bounds {
+                min: min.map(fit).transpose()?,
+                max: fit(max)?,
+        })
+}
+
+/// Everything is worked out in (checked) u128, then narrowed by the caller.
+fn parse_u128(input: &str) -> Result<u128, BoundsError> {
+        let original = input.trim();
+        let cleaned: String = original.chars().filter(|&c| c != '_').collect();
+        let invalid = || BoundsError::Invalid { input: original.to_string() };
+        let overflow = || BoundsError::Overflow {
+                input:  original.to_string(),
+                target: type_name::<u128>(),
+        };
+        if cleaned.is_empty() {
+                return Err(BoundsError::Empty);
+        }
+        if cleaned.starts_with('-') {
+                return Err(BoundsError::Negative { input: original.to_string() });
+        }
+        let cleaned = cleaned.strip_prefix('+').unwrap_or(&cleaned);
+
+        if let Some((base, exp)) = cleaned.split_once('^') {
+                let base = parse_u128(base)?;
+                let exp: u32 = exp.parse().map_err(|_| invalid())?;
+                return base.checked_pow(exp).ok_or_else(overflow);
+        }
+
+        // mantissa × 10^exp, from a suffix and/or an `e` exponent
+        let (mantissa, mut exp): (_, u32) = match cleaned.char_indices().last() {
+                | Some((i, 'k' | 'K')) => (&cleaned[..i], 3),
+                | Some((i, 'M')) => (&cleaned[..i], 6),
+                | Some((i, 'G')) => (&cleaned[..i], 9),
+                | Some((i, 'T')) => (&cleaned[..i], 12),
+                | _ => (cleaned, 0),
+        };
+        let (mantissa, e) = match mantissa.split_once(['e', 'E']) {
+                | Some((mantissa, e)) => (mantissa, Some(e.strip_prefix('+').unwrap_or(e))),
+                | None => (mantissa, None),
+        };
+        let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if whole.is_empty() && fraction.is_empty() || !all_digits(whole) || !all_digits(fraction) {
+                return Err(invalid());
+        }
+        if let Some(e) = e {
+                if e.is_empty() || !all_digits(e) {
+                        return Err(invalid());
+                }
+                // zero stays zero, however large the exponent
+                if whole.bytes().chain(fraction.bytes()).all(|b| b == b'0') {
+                        return Ok(0);
+                }
+                // all digits, so the only way to fail is being too large
+                exp = e.parse::<u32>().ok().and_then(|e| exp.checked_add(e)).ok_or_else(overflow)?;
+        }
+        // only as many fraction digits as the exponent can shift left; the rest must be zeros
+        let shift = (exp as usize).min(fraction.len());
+        let (kept, dropped) = fraction.split_at(shift);
+        if dropped.bytes().any(|b| b != b'0') {
+                return Err(BoundsError::NotWhole { input: original.to_string() });
+        }
+        let digits = format!("{whole}{kept}");
+        let digits = digits.trim_start_matches('0');
+        let significand: u128 = if digits.is_empty() { 0 } else { digits.parse().map_err(|_| overflow())? };
+        let scale = 10_u128.checked_pow(exp - shift as u32).ok_or_else(overflow)?;
+        significand.checked_mul(scale).ok_or_else(overflow)
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        #[test]
+        fn friendly_forms() {
+                let cases: [(&str, u64); 16] = [
+                        ("12345", 12_345),
+                        ("100_000_000", 100_000_000),
+                        (" 1_000 ", 1_000),
+                        ("1e9", 1_000_000_000),
+                        ("1.5E6", 1_500_000),
+                        ("2e+3", 2_000),
+                        ("2^32", 4_294_967_296),
+                        ("10^12", 1_000_000_000_000),
+                        ("10k", 10_000),
+                        ("2.5M", 2_500_000),
+                        ("1G", 1_000_000_000),
+                        ("3T", 3_000_000_000_000),
+                        ("1.50k", 1_500),
+                        ("0", 0),
+                        ("0e4294967295", 0),
+                        ("0.00e99999999999k", 0),
+                ];
+                for (input, expected) in cases {
+                        assert_eq!(parse::<u64>(input), Ok(expected), "{input:?}");
+                }
+        }
+
+        #[test]
+        fn rejects_clearly() {
+                assert_eq!(parse::<u64>(""), Err(BoundsError::Empty));
+                assert!(matches!(parse::<u64>("ten"), Err(BoundsError::Invalid { .. })));
+                assert!(matches!(parse::<u64>("1e"), Err(BoundsError::Invalid { .. })));
+                assert!(matches!(parse::<u64>("k"), Err(BoundsError::Invalid { .. })));
+                assert!(matches!(parse::<u64>("-5"), Err(BoundsError::Negative { .. })));
+                assert!(matches!(parse::<u64>("1.5"), Err(BoundsError::NotWhole { .. })));
+                assert!(matches!(parse::<u64>("1.2345k"), Err(BoundsError::NotWhole { .. })));
+                assert!(matches!(parse::<u64>("2^64"), Err(BoundsError::Overflow { target: "u64", .. })));
+                assert!(matches!(parse::<u32>("5G"), Err(BoundsError::Overflow { target: "u32", .. })));
+                assert!(matches!(parse::<u128>("1e40"), Err(BoundsError::Overflow { .. })));
+                assert!(matches!(parse::<u128>("2^200"), Err(BoundsError::Overflow { .. })));
+                // the suffix pushes the exponent itself past `u32`
+                assert!(matches!(parse::<u128>("1e4294967295k"), Err(BoundsError::Overflow { .. })));
+                assert!(matches!(parse::<u128>("1e99999999999"), Err(BoundsError::Overflow { .. })));
+                assert!(matches!(parse::<u64>("1e+"), Err(BoundsError::Invalid { .. })));
+                assert_eq!(parse::<u64>("18_446_744_073_709_551_615"), Ok(u64::MAX));
+        }
+
+        #[test]
+        fn ranges() {
+                let bounds = |min, max| Ok(Bounds { min, max });
+                assert_eq!(parse_bounds::<usize>("1e3"), bounds(None, 1_000));
+                assert_eq!(parse_bounds::<usize>("9G..=12G"), bounds(Some(9_000_000_000), 12_000_000_000));
+                assert_eq!(parse_bounds::<usize>("10..20"), bounds(Some(10), 19));
+                assert_eq!(parse_bounds::<usize>("..=2^10"), bounds(None, 1_024));
+                assert_eq!(parse_bounds::<usize>("5..=5"), bounds(Some(5), 5));
+                assert!(matches!(parse_bounds::<usize>("20..=10"), Err(BoundsError::Reversed { .. })));
+                assert!(matches!(parse_bounds::<usize>("5..5"), Err(BoundsError::Reversed { .. })));
+                assert!(matches!(parse_bounds::<usize>("..0"), Err(BoundsError::Reversed { .. })));
+                assert!(matches!(parse_bounds::<u8>("1..=300"), Err(BoundsError::Overflow { target: "u8", .. })));
+        }
+}
@@ -0,0 +1,397 @@
+//! # prime-sieves
+//! The sieves of Eratosthenes behind `util_prime-sieve`, `comp_prime-sieve-bitvec` and `comp_prime-sieve-bench`,
+//! so the benchmark times (and cross-validates) the same code the scripts run.
+//!
+//! Every sieve returns the primes of `[min, max]` (`min` defaulting to 0), ascending:
+//!
+//! | sieve                     | storage                                                          |
+//! |---------------------------|------------------------------------------------------------------|
+//! | [`prime_sieve`]           | `Vec<bool>`: one byte per number                                 |
+//! | [`segmented_prime_sieve`] | base primes to `isqrt(max)`, then blocks ([`SegmentedPrimes`])   |
+//! | [`wheel_prime_sieve`]     | mod-30 wheel, over any [`WheelFlags`] (`Vec<bool>`, [`PackedWheel`], `BitVec`) |
+//! | [`bitvec_prime_sieve`]    | `bitvec`: one bit per number, cleared one at a time              |
+//! | [`word_prime_sieve`]      | `bitvec`'s raw `u64` words, small primes struck with masks       |
+//! | [`u64_words_prime_sieve`] | hand-rolled `Vec<u64>` bitset                                    |
+//! | [`odd_only_prime_sieve`]  | one byte per odd number                                          |
+//!
+//! ## Use (from a script's frontmatter)
+//! ```toml
+//! [dependencies]
+//! prime-sieves = { path = "../crate-libs/prime-sieves" }
+//! ```
+//! ```rust,ignore
+//! let primes = prime_sieves::wheel_prime_sieve::<prime_sieves::PackedWheel>(None, 1_000_000);
+//! ```
+use std::borrow::Cow;
+
+use bitvec::prelude::*;
+
+/// Numbers sieved per block by the segmented sieve.  (A flag per number, sized to sit in L2.)
+pub const SEGMENT_SIZE: usize = 1 << 18;
+/// Primes below this strike with repeating word masks; above it, with strided word writes.
+const MASK_PRIME_LIMIT: usize = 64;
+/// Residues mod 30 that are coprime to 2, 3 and 5.  Each one is a wheel position.
+pub const WHEEL_RESIDUES: [usize; 8] = [1, 7, 11, 13, 17, 19, 23, 29];
+/// Position of each residue mod 30 within a wheel turn.  (`usize::MAX` for residues the wheel skips.)
+const WHEEL_INDEX: [usize; 30] = {
+        let mut index = [usize::MAX; 30];
+        let mut i = 0;
+        while i < WHEEL_RESIDUES.len() {
+                index[WHEEL_RESIDUES[i]] = i;
+                i += 1;
+        }
+        index
+};
+
+/// I'll be surprised if this works efficiently as a mechanical, literal, procedure.
+pub fn prime_sieve(min: Option<usize>, max: usize) -> Vec<usize> {
+        // buncha default yes's
+        let mut primes = vec![true; max + 1];
+        primes[0] = false;
+        if let Some(one) = primes.get_mut(1) {
+                *one = false;
+        }
+        // no need to go past sqrt(n).floor()
+        for i in  2..=max.isqrt() {
+                // skip if index was marked as multiple of preceding num
+                if primes[i] {
+                        // first value that's not been sieved would require p >= us, which would be us
+                        let mut index = i * i;
+                        // false for al p * n indices
+                        while index <= max {
+                                primes[index] = false;
+                                index += i;
+                        }
+                }
+        }
+        let min = min.unwrap_or(0);
+        // collect unsieved bits
+        let mut result = vec![];
+        for (i, b) in primes.iter().enumerate().skip(min) {
+                if *b {
+                        result.push(i);
+                }
+        }
+        result
+}
+
+/// Same results as `prime_sieve`, but only ever holds `isqrt(max)` base primes and one block of flags.
+pub fn segmented_prime_sieve(min: Option<usize>, max: usize) -> Vec<usize> {
+        SegmentedPrimes::new(min.unwrap_or(0), max).collect()
+}
+
+/// Segmented sieve as an iterator: `[min, max]` is sieved one block at a time, as primes are asked for.
+pub struct SegmentedPrimes<'a> {
+        base_primes: Cow<'a, [usize]>,
+        /// flags for the current block
+        segment:     Vec<bool>,
+        /// number at `segment[0]`
+        low:         usize,
+        /// next index of `segment` to look at
+        pos:         usize,
+        /// start of the next block, if any
+        next_low:    Option<usize>,
+        max:         usize,
+}
+impl SegmentedPrimes<'static> {
+        pub fn new(min: usize, max: usize) -> Self {
+                // every composite in range has a factor no larger than this
+                let base_primes = prime_sieve(None, max.isqrt());
+                Self::with_base_primes(base_primes, min, max)
+        }
+}
+impl<'a> SegmentedPrimes<'a> {
+        /// `base_primes` must hold every prime up to `isqrt(max)`.
+        pub fn with_base_primes(base_primes: impl Into<Cow<'a, [usize]>>, min: usize, max: usize) -> Self {
+                let min = min.max(2);
+                Self {
+                        base_primes: base_primes.into(),
+                        segment:     vec![],
+                        low:         min,
+                        pos:         0,
+                        next_low:    (min <= max).then_some(min),
+                        max,
+                }
+        }
+        /// Sieves the next block into `segment`.  `false` once the range is used up.
+        fn sieve_next_block(&mut self) -> bool {
+                let Some(low) = self.next_low else {
+                        return false;
+                };
+                let high = self.max.min(low.saturating_add(SEGMENT_SIZE - 1));
+                self.segment.clear();
+                self.segment.resize(high - low + 1, true);
+                for &p in self.base_primes.iter().take_while(|&&p| p * p <= high) {
+                        // first multiple of p in the block; below p*p was already covered by smaller primes
+                        let first = (p * p).max(low.div_ceil(p) * p);
+                        for index in (first..=high).step_by(p) {
+                                self.segment[index - low] = false;
+                        }
+                }
+                self.low = low;
+                self.pos = 0;
+                self.next_low = (high < self.max).then(|| high + 1);
+                true
+        }
+}
+impl Iterator for SegmentedPrimes<'_> {
+        type Item = usize;
+        fn next(&mut self) -> Option<usize> {
+                loop {
+                        if let Some(offset) = self.segment[self.pos..].iter().position(|&b| b) {
+                                let p = self.low + self.pos + offset;
+                                self.pos += offset + 1;
+                                return Some(p);
+                        }
+                        if !self.sieve_next_block() {
+                                return None;
+                        }
+                }
+        }
+}
+
+/// Number held at a wheel position.
+pub fn wheel_value(pos: usize) -> usize {
+        30 * (pos / 8) + WHEEL_RESIDUES[pos % 8]
+}
+/// Wheel position of a number coprime to 30.
+pub fn wheel_pos(n: usize) -> usize {
+        8 * (n / 30) + WHEEL_INDEX[n % 30]
+}
+/// Count of wheel positions holding numbers `<= n`.
+pub fn wheel_len(n: usize) -> usize {
+        8 * (n / 30) + WHEEL_RESIDUES.iter().filter(|&&r| r <= n % 30).count()
+}
+
+/// Flag storage indexed by wheel position.
+pub trait WheelFlags {
+        fn all_set(len: usize) -> Self;
+        fn is_set(&self, pos: usize) -> bool;
+        fn unset(&mut self, pos: usize);
+}
+impl WheelFlags for Vec<bool> {
+        fn all_set(len: usize) -> Self {
+                vec![true; len]
+        }
+        fn is_set(&self, pos: usize) -> bool {
+                self[pos]
+        }
+        fn unset(&mut self, pos: usize) {
+                self[pos] = false;
+        }
+}
+/// One byte per wheel turn: bit `j` of byte `k` is the number `30k + WHEEL_RESIDUES[j]`.
+pub struct PackedWheel(Vec<u8>);
+impl WheelFlags for PackedWheel {
+        fn all_set(len: usize) -> Self {
+                Self(vec![u8::MAX; len.div_ceil(8)])
+        }
+        fn is_set(&self, pos: usize) -> bool {
+                self.0[pos / 8] & (1 << (pos % 8)) != 0
+        }
+        fn unset(&mut self, pos: usize) {
+                self.0[pos / 8] &= !(1 << (pos % 8));
+        }
+}
+/// One bit per wheel position, via `bitvec`.
+impl WheelFlags for BitVec {
+        fn all_set(len: usize) -> Self {
+                bitvec![1; len]
+        }
+        fn is_set(&self, pos: usize) -> bool {
+                self[pos]
+        }
+        fn unset(&mut self, pos: usize) {
+                self.set(pos, false);
+        }
+}
+
+/// Sieve of Eratosthenes over the mod-30 wheel.  2, 3 and 5 are never stored, so they're added by hand.
+pub fn wheel_prime_sieve<F: WheelFlags>(min: Option<usize>, max: usize) -> Vec<usize> {
+        let len = wheel_len(max);
+        let mut flags = F::all_set(len);
+        // position 0 holds 1, which is not prime
+        if len > 0 {
+                flags.unset(0);
+        }
+        for pos in 1..len {
+                let p = wheel_value(pos);
+                if p * p > max {
+                        break;
+                }
+                if flags.is_set(pos) {
+                        // multiples sharing a factor with 30 were never stored, so only walk wheel multipliers
+                        for m in (pos..).map(wheel_value).take_while(|&m| p * m <= max) {
+                                flags.unset(wheel_pos(p * m));
+                        }
+                }
+        }
+        let min = min.unwrap_or(0);
+        let mut result: Vec<usize> = [2, 3, 5].into_iter().filter(|&p| min <= p && p <= max).collect();
+        // `min` past `max` leaves `start..len` empty
+        let start = if min == 0 { 0 } else { wheel_len(min - 1) };
+        for pos in start..len {
+                if flags.is_set(pos) {
+                        result.push(wheel_value(pos));
+                }
+        }
+        result
+}
+
+/// The literal sieve again, one bit per number: cleared one at a time through `bitvec`'s `get_mut` proxy.
+pub fn bitvec_prime_sieve(min: Option<usize>, max: usize) -> Vec<usize> {
+        let mut primes: BitVec = bitvec![1; max + 1];
+        *primes.get_mut(0).unwrap() = false;
+        if let Some(mut one) = primes.get_mut(1) {
+                *one = false;
+        }
+        for i in 2..=max.isqrt() {
+                if primes[i] {
+                        let mut index = i * i;
+                        while index <= max {
+                                *primes.get_mut(index).unwrap() = false;
+                                index += i;
+                        }
+                }
+        }
+        primes.iter_ones().skip_while(|&i| i < min.unwrap_or(0)).collect()
+}
+
+/// Same sieve, striking whole `u64` words at a time.  Bit `i` of word `w` is the number `64w + i`.
+pub fn word_prime_sieve(min: Option<usize>, max: usize) -> Vec<usize> {
+        let mut primes: BitVec<u64, Lsb0> = BitVec::repeat(true, max + 1);
+        let words = primes.as_raw_mut_slice();
+        // 0 and 1
+        words[0] &= !0b11;
+        for i in 2..=max.isqrt() {
+                if words[i / 64] & (1 << (i % 64)) == 0 {
+                        continue;
+                }
+                if i < MASK_PRIME_LIMIT {
+                        // 64·i bits hold a whole number of multiples of i, so the pattern repeats every i words
+                        let masks: Vec<u64> = (0..i)
+                                .map(|w| (0..64).filter(|bit| (64 * w + bit) % i == 0).fold(0, |mask, bit| mask | 1 << bit))
+                                .collect();
+                        for (word, mask) in words.iter_mut().zip(masks.iter().cycle()) {
+                                *word &= !mask;
+                        }
+                        // i itself was struck with its multiples
+                        words[0] |= 1 << i;
+                } else {
+                        for index in (i * i..=max).step_by(i) {
+                                words[index / 64] &= !(1 << (index % 64));
+                        }
+                }
+        }
+        // collect unsieved bits; storage past `max` is padding, so stop there
+        let min = min.unwrap_or(0);
+        let mut result = vec![];
+        for (w, &word) in words.iter().enumerate().skip(min / 64) {
+                let mut bits = if w == min / 64 { word & (u64::MAX << (min % 64)) } else { word };
+                while bits != 0 {
+                        let n = 64 * w + bits.trailing_zeros() as usize;
+                        if n > max {
+                                break;
+                        }
+                        result.push(n);
+                        bits &= bits - 1;
+                }
+        }
+        result
+}
+
+/// One bit per number in plain `u64` words: direct word writes, no masks.
+pub fn u64_words_prime_sieve(min: Option<usize>, max: usize) -> Vec<usize> {
+        let mut words = vec![u64::MAX; (max + 1).div_ceil(64)];
+        // bits past `max` in the last word, plus 0 and 1
+        if let Some(last) = words.last_mut() {
+                let used = (max + 1) % 64;
+                if used != 0 {
+                        *last &= (1 << used) - 1;
+                }
+        }
+        words[0] &= !0b11;
+        let is_set = |words: &[u64], i: usize| words[i / 64] & (1 << (i % 64)) != 0;
+        for i in 2..=max.isqrt() {
+                if is_set(&words, i) {
+                        for index in (i * i..=max).step_by(i) {
+                                words[index / 64] &= !(1 << (index % 64));
+                        }
+                }
+        }
+        let min = min.unwrap_or(0);
+        let mut primes = vec![];
+        for (w, &word) in words.iter().enumerate() {
+                let mut bits = word;
+                while bits != 0 {
+                        let n = 64 * w + bits.trailing_zeros() as usize;
+                        if n >= min {
+                                primes.push(n);
+                        }
+                        bits &= bits - 1;
+                }
+        }
+        primes
+}
+
+/// One byte per odd number: index `i` is `2i + 1`.
+pub fn odd_only_prime_sieve(min: Option<usize>, max: usize) -> Vec<usize> {
+        if max < 2 {
+                return vec![];
+        }
+        let mut odd = vec![true; max.div_ceil(2)];
+        odd[0] = false;
+        for i in (3..=max.isqrt()).step_by(2) {
+                if odd[i / 2] {
+                        // odd multiples only: i², i² + 2i, ...
+                        for index in (i * i..=max).step_by(2 * i) {
+                                odd[index / 2] = false;
+                        }
+                }
+        }
+        let odd_primes = odd.iter().enumerate().filter(|(_, b)| **b).map(|(i, _)| 2 * i + 1);
+        std::iter::once(2).chain(odd_primes).filter(|&p| p >= min.unwrap_or(0)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        #[test]
+        fn segmented_matches_simple() {
+                let max = 3 * SEGMENT_SIZE + 17;
+                for min in [None, Some(0), Some(2), Some(3), Some(SEGMENT_SIZE - 1), Some(2 * SEGMENT_SIZE + 5), Some(max)] {
+                        assert_eq!(segmented_prime_sieve(min, max), prime_sieve(min, max), "min: {min:?}");
+                }
+                for max in 0..50 {
+                        assert_eq!(segmented_prime_sieve(None, max), prime_sieve(None, max), "max: {max}");
+                }
+        }
+
+        #[test]
+        fn wheels_match_simple() {
+                let wheel_edges = [1, 2, 10, 1_000].into_iter().flat_map(|k| [30 * k - 1, 30 * k, 30 * k + 1]);
+                for max in (0..200).chain(wheel_edges.clone()).chain([9_999, 123_457]) {
+                        let mins = [None, Some(0), Some(1), Some(2), Some(6), Some(max / 2), Some(max), Some(max + 1)];
+                        for min in mins.into_iter().chain(wheel_edges.clone().map(Some)) {
+                                let expected = prime_sieve(min, max);
+                                assert_eq!(wheel_prime_sieve::<Vec<bool>>(min, max), expected, "{min:?}..={max}");
+                                assert_eq!(wheel_prime_sieve::<PackedWheel>(min, max), expected, "{min:?}..={max}");
+                                assert_eq!(wheel_prime_sieve::<BitVec>(min, max), expected, "{min:?}..={max}");
+                        }
+                }
+        }
+
+        #[test]
+        fn bit_and_byte_layouts_match_simple() {
+                for max in [0, 1, 2, 3, 63, 64, 65, 127, 128, 4_096, 4_099, 100_003] {
+                        for min in [None, Some(0), Some(1), Some(63), Some(64), Some(max / 2), Some(max)] {
+                                let expected = prime_sieve(min, max);
+                                assert_eq!(bitvec_prime_sieve(min, max), expected, "bitvec, {min:?}..={max}");
+                                assert_eq!(word_prime_sieve(min, max), expected, "words, {min:?}..={max}");
+                                assert_eq!(u64_words_prime_sieve(min, max), expected, "u64 words, {min:?}..={max}");
+                                assert_eq!(odd_only_prime_sieve(min, max), expected, "odd only, {min:?}..={max}");
+                        }
+                }
+        }
+}
@@ -0,0 +1,188 @@
+#!/usr/bin/env -S cargo +nightly -Zscript
+---
+package.edition = "2024"
+profile.dev.opt-level = 3
+profile.dev.package."*".opt-level = 3
+[dependencies]
+bitvec = "1.0.1"
+clap = { version = "4", features = ["derive"] }
+numeric-bounds = { path = "../crate-libs/numeric-bounds" }
+owo-colors = "4.1"
+prime-sieves = { path = "../crate-libs/prime-sieves" }
+---
+//! # Compare: prime sieve storage strategies, in-process
+//!
+//! `util_prime-sieve` vs `comp_prime-sieve-bitvec` timings were eyeballed across two scripts,
+//! and at low bounds the numbers were mostly cargo startup.
+//! This runs every strategy in one process, over a list of bounds,
+//! with warmups and repeated iterations, reporting median and mean ± stddev.
+//!
+//! Before anything is timed, every strategy must return exactly the same primes as `Vec<bool>`.
+//!
+//! ## Strategies
+//! All from `crate-libs/prime-sieves`, the same code the scripts run:
+//! - `vec-bool`: one byte per number (`util_prime-sieve --sieve simple`)
+//! - `bitvec`: one bit per number via `bitvec`, set through `get_mut` (`comp_prime-sieve-bitvec --naive`)
+//! - `bitvec-words`: word masks for small primes on bitvec's raw `u64`s (`comp_prime-sieve-bitvec`)
+//! - `u64-words`: hand-rolled `Vec<u64>` bitset; direct word writes, `trailing_zeros` collection
+//! - `odd-only`: one byte per odd number
+//! - `segmented`: base primes to `isqrt(max)`, then cache-sized blocks (`util_prime-sieve` default)
+//! - `wheel`, `wheel-packed`: mod-30 wheel, a byte per position or 8 positions per byte (`util_prime-sieve --sieve wheel[-packed]`)
+//! - `wheel-bitvec`: mod-30 wheel, a bit per position via `bitvec` (`comp_prime-sieve-bitvec --wheel`)
+//!
+//! (Opt-level is raised in the header, so plain debug runs are already optimized.)
+//!
+//! ## Convenience Section
+//!
+//! ### Shell Commands
+//! - direct
+//!   - `chmod u+x comp_prime-sieve-bench.rs`
+//!   - `./comp_prime-sieve-bench.rs 1e6 1e7 1e8 --iterations 5`
+//! - via cargo
+//!   - `cargo +nightly -Zscript comp_prime-sieve-bench.rs`
+//! - other cargo commands
+//!   - `cargo +nightly -Zscript COMMAND *ARGS --manifest-path comp_prime-sieve-bench.rs`
+//!
+//! ### Links
+//! - [Cargo Book: Script](https://doc.rust-lang.org/nightly/cargo/reference/unstable.html#script)
+//! - [Github: Cargo-Script Tracking](https://github.com/rust-lang/cargo/issues/12207)
+use std::{error::Error,
+          hint::black_box,
+          result::Result,
+          time::{Duration, Instant}};
+
+use bitvec::vec::BitVec;
+use clap::Parser;
+use numeric_bounds::parse;
+use owo_colors::OwoColorize;
+use prime_sieves::{PackedWheel, bitvec_prime_sieve, odd_only_prime_sieve, prime_sieve, segmented_prime_sieve, u64_words_prime_sieve,
+                   wheel_prime_sieve, word_prime_sieve};
+
+/// Primes in `[min, max]` (inclusive), ascending.  Always timed from 0 here.
+type Sieve = fn(Option<usize>, usize) -> Vec<usize>;
+
+/// Every strategy, by name; the first is the reference for cross-validation.
+const STRATEGIES: [(&str, Sieve); 9] = [
+        ("vec-bool", prime_sieve),
+        ("bitvec", bitvec_prime_sieve),
+        ("bitvec-words", word_prime_sieve),
+        ("u64-words", u64_words_prime_sieve),
+        ("odd-only", odd_only_prime_sieve),
+        ("segmented", segmented_prime_sieve),
+        ("wheel", wheel_prime_sieve::<Vec<bool>>),
+        ("wheel-packed", wheel_prime_sieve::<PackedWheel>),
+        ("wheel-bitvec", wheel_prime_sieve::<BitVec>),
+];
+
+/// In-process benchmark of prime sieve storage strategies
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Args {
+        /// Bounds to sieve up to (inclusive)
+        #[arg(value_parser = parse::<usize>, default_values = ["1e5", "1e6", "1e7"])]
+        bounds:     Vec<usize>,
+        /// Untimed runs per strategy & bound
+        #[arg(short, long, default_value_t = 2)]
+        warmups:    usize,
+        /// Timed runs per strategy & bound
+        #[arg(short, long, default_value_t = 7, value_parser = clap::value_parser!(u32).range(1..))]
+        iterations: u32,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+        let args = Args::parse();
+
+        println!("{}", "Cross-validating against vec-bool...".cyan());
+        for &max in &args.bounds {
+                cross_validate(max)?;
+        }
+        println!("all {} strategies agree on {:?}\n", STRATEGIES.len().green(), args.bounds);
+
+        println!(
+                "{:>14} {:>12} {:>12} {:>22} {:>8}",
+                "bound", "strategy", "median", "mean ± stddev", "vs best"
+        );
+        for &max in &args.bounds {
+                let stats: Vec<_> = STRATEGIES
+                        .iter()
+                        .map(|&(name, sieve)| (name, Stats::of(&time_runs(sieve, max, args.warmups, args.iterations))))
+                        .collect();
+                let best = stats.iter().map(|(_, s)| s.median).min().expect("at least one strategy");
+                for (name, s) in &stats {
+                        let ratio = s.median.as_secs_f64() / best.as_secs_f64();
+                        let mean_sd = format!("{:.3?} ± {:.3?}", s.mean, s.stddev);
+                        let ratio = format!("{ratio:.2}x");
+                        let ratio = if s.median == best { ratio.green().to_string() } else { ratio.yellow().to_string() };
+                        println!("{:>14} {:>12} {:>12} {:>22} {:>8}", max.blue(), name, format!("{:.3?}", s.median), mean_sd, ratio);
+                }
+        }
+        Ok(())
+}
+
+/// Every strategy must return exactly what `vec-bool` does.
+fn cross_validate(max: usize) -> Result<(), Box<dyn Error>> {
+        let (reference_name, reference_sieve) = STRATEGIES[0];
+        let expected = reference_sieve(None, max);
+        for &(name, sieve) in &STRATEGIES[1..] {
+                let found = sieve(None, max);
+                if found != expected {
+                        let first_diff = found.iter().zip(&expected).position(|(a, b)| a != b);
+                        Err(format!(
+                                "`{name}` disagrees with `{reference_name}` up to {max}: {} vs {} primes, first difference at index {:?}",
+                                found.len(),
+                                expected.len(),
+                                first_diff
+                        ))?
+                }
+        }
+        Ok(())
+}
+
+fn time_runs(sieve: Sieve, max: usize, warmups: usize, iterations: u32) -> Vec<Duration> {
+        for _ in 0..warmups {
+                black_box(sieve(None, black_box(max)));
+        }
+        (0..iterations)
+                .map(|_| {
+                        let start = Instant::now();
+                        black_box(sieve(None, black_box(max)));
+                        start.elapsed()
+                })
+                .collect()
+}
+
+struct Stats {
+        median: Duration,
+        mean:   Duration,
+        /// sample standard deviation (zero for a single run)
+        stddev: Duration,
+}
+impl Stats {
+        fn of(runs: &[Duration]) -> Self {
+                let mut sorted = runs.to_vec();
+                sorted.sort_unstable();
+                let mid = sorted.len() / 2;
+                let median = if sorted.len() % 2 == 1 { sorted[mid] } else { (sorted[mid - 1] + sorted[mid]) / 2 };
+                let secs: Vec<f64> = runs.iter().map(Duration::as_secs_f64).collect();
+                let mean = secs.iter().sum::<f64>() / secs.len() as f64;
+                let variance = secs.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (secs.len().max(2) - 1) as f64;
+                Self {
+                        median,
+                        mean: Duration::from_secs_f64(mean),
+                        stddev: Duration::from_secs_f64(variance.sqrt()),
+                }
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        #[test]
+        fn strategies_agree_on_small_bounds() {
+                // 0, 1 & 2 have the edge cases; around 30·k and 64·k the wheel and word layouts turn over
+                for max in (0..=130).chain([899, 900, 901, 4_095, 4_096, 4_097, 100_003]) {
+                        cross_validate(max).unwrap();
+                }
+        }
+}
@@ -4,6 +4,8 @@ package.edition = "2024"
 [dependencies]
 clap = { version = "4", features = ["derive"] }
 bitvec = "1.0.1"
+numeric-bounds = { path = "../crate-libs/numeric-bounds" }
+prime-sieves = { path = "../crate-libs/prime-sieves" }
 ---
 //! # Compare w/ `util_prime-sieve` 
 //! bitvec replacement for Vec<bool> 
@@ -15,37 +17,64 @@ bitvec = "1.0.1"
 //! Low times are dominated by the cargo call machinery.
 //! But at 1billion in release we're looking at about 7 vs 10 seconds.
 //!
-//! ## Alt
-//! Might pre-calculate per-byte setting.  Not that interested in pursuing this without setting up better assembly level visualization.
-//! 
-//! 
+//! ## Wheel (`--wheel`)
+//! Only numbers coprime to 2·3·5 get a bit (8 of every 30), so ~3.75x fewer bits and far fewer strikes.
+//! That reverses the result above: at 1billion in release (slower box than the numbers above)
+//! plain bitvec ~15s, `util_prime-sieve --sieve simple` ~24s, `--wheel` bitvec ~6.5s.
+//!
+//! ## Words (default; `--naive` for the above)
+//! The per-byte pre-calculation idea, done per `u64` word on bitvec's raw storage.
+//! Primes below 64 strike with a precomputed mask: multiples of `p` repeat every `p` words,
+//! so `p` masks cover the whole vector, one `&=` per word.  (Bit `p` itself is restored afterwards.)
+//! Larger primes strike at most once per word anyway, so they write words directly at stride `p`.
+//! Primes are collected with `trailing_zeros` over each word rather than `iter().enumerate()`.
+//! At 1billion in release (same box as the wheel numbers): ~10s vs ~15.5s for `--naive`.
+//! The large-prime strides still miss cache on every write, which is why `--wheel` stays ahead.
+//!
+//! (The sieves themselves live in `crate-libs/prime-sieves`, shared with `util_prime-sieve` and `comp_prime-sieve-bench`.)
+//!
 //! ### Links
 //! - [Cargo Book: Script](https://doc.rust-lang.org/nightly/cargo/reference/unstable.html#script)
 //! - [Github: Cargo-Script Tracking](https://github.com/rust-lang/cargo/issues/12207)
 use std::{error::Error, result::Result};
 
-use bitvec::prelude::*;
+use bitvec::vec::BitVec;
 use clap::Parser;
+use numeric_bounds::{Bounds, parse, parse_bounds};
+use prime_sieves::{bitvec_prime_sieve, wheel_prime_sieve, word_prime_sieve};
 
 /// scratch_prime Cargo-Script
 #[derive(Parser, Debug)]
 #[command(version, about)]
 struct Args {
-        /// Calculate all primes till some number
-        primes_till: Option<usize>,
+        /// Calculate all primes till some number (`1e9`, `2^32`, `10k`, ... welcome).  Or a range: `min..=max`
+        #[arg(value_parser = parse_bounds::<usize>)]
+        primes_till: Option<Bounds<usize>>,
 
         /// Only show primes above this number
-        #[arg(short='n', long="min")]
+        #[arg(short='n', long="min", value_parser = parse::<usize>)]
         primes_from: Option<usize>,
 
         /// Show all primes found
         #[arg(short, long)]
-        show: bool
+        show: bool,
+        /// Only store numbers coprime to 2·3·5 (mod-30 wheel, one byte per 30 numbers)
+        #[arg(short, long, conflicts_with = "naive")]
+        wheel: bool,
+        /// Clear bits one at a time via `get_mut` instead of word-level masks (for comparison)
+        #[arg(long)]
+        naive: bool,
 }
 fn main() -> Result<(), Box<dyn Error>> {
-        let args = Args::parse();
+        let mut args = Args::parse();
+        if let Some(Bounds { min: Some(min), .. }) = args.primes_till {
+                if args.primes_from.is_some() {
+                        Err("Error: give a minimum with `--min` or as `min..=max`, not both.")?
+                }
+                args.primes_from = Some(min);
+        }
         let primes_from = args.primes_from.unwrap_or(0);
-        let primes_till = match args.primes_till {
+        let primes_till = match args.primes_till.map(|bounds| bounds.max) {
                 None => {
                         println!("Hi from scratch_prime.rs.  No primes_till given, defaulting to : 12_345");
                         12_345
@@ -59,7 +88,13 @@ fn main() -> Result<(), Box<dyn Error>> {
         println!("Calculating primes from ({primes_from}..={primes_till})...");
         if primes_from > primes_till { Err("Error: your minimum is larger than your maximum.  Cancelling search.")? };
 
-        let found_primes = prime_sieve(args.primes_from, primes_till);
+        let found_primes = if args.wheel {
+                wheel_prime_sieve::<BitVec>(args.primes_from, primes_till)
+        } else if args.naive {
+                bitvec_prime_sieve(args.primes_from, primes_till)
+        } else {
+                word_prime_sieve(args.primes_from, primes_till)
+        };
         println!("Number of primes found <= {primes_till}: {}", found_primes.len());
         println!("which makes the range ({primes_from}..={primes_till}) {:.1}% prime.", 100.*(found_primes.len() as f32)/(primes_till as f32 + 2.));
         if args.show {
@@ -67,34 +102,3 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
         Ok(())
 }
-
-/// I'll be surprised if this works efficiently as a mechanical, literal, procedure.
-fn prime_sieve(min: Option<usize>, max: usize) -> Vec<usize> {
-        // buncha default yes's
-        // let mut primes = vec![true; max + 1];
-        let mut primes: BitVec = bitvec![1; max + 1];
-        *primes.get_mut(0).unwrap() = false;
-        *primes.get_mut(1).unwrap() = false;
-        // no need to go past sqrt(n).floor()
-        for i in  2..=max.isqrt() {
-                // skip if index was marked as multiple of preceding num
-                if primes[i] {
-                        // first value that's not been sieved would require p >= us, which would be us
-                        let mut index = i * i;
-                        // false for al p * n indices
-                        while index <= max {
-                                *primes.get_mut(index).unwrap() = false;
-                                index += i;
-                        }
-                }
-        }
-        let min = min.unwrap_or(0);
-        // collect unsieved bits
-        let mut result = vec![];
-        for (i, b) in primes.iter().enumerate().skip(min) {
-                if *b {
-                        result.push(i);
-                }
-        }
-        result
-}
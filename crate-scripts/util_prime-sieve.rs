@@ -3,20 +3,69 @@
 package.edition = "2024"
 [dependencies]
 clap = { version = "4", features = ["derive"] }
+memmap2 = "0.9"
+numeric-bounds = { path = "../crate-libs/numeric-bounds" }
 owo-colors = "4.1"
+prime-sieves = { path = "../crate-libs/prime-sieves" }
+serde = { version = "1", features = ["derive"] }
+serde_json = "1"
 ---
 //! # Cargo-Script: util_prime-sieve
 //!
 //! Gets slow around 100 million in debug mode and 1 billion in release mode.
 //! 100_000_000
 //! 1_000_000_000
-//! (note: doesn't seem to be a way to enter numbers with `_` separators via cli for clap)
+//! (clap alone won't take `_` separators; bounds go through `crate-libs/numeric-bounds`, so
+//! `100_000_000`, `1e9`, `2^32`, `10k`, and ranges like `9e9..=12e9` all work)
 //!
 //! Faster than I'd have guessed given its rather ... direct approach.
 //!
 //! (I wonder if the index calculations, skipping indices with false and jumping to P*n indicces, have any special
 //! performances advantages.)
 //!
+//! ## Segmented
+//! The literal sieve needs a flag for every number up to `max`, so a window like `--min 9000000000 12000000000`
+//! wants tens of GB.  The segmented sieve (default) only sieves base primes up to `isqrt(max)` and then walks
+//! `[min, max]` in cache-sized blocks, so memory scales with the window and `isqrt(max)` instead.
+//! Use `--sieve simple` for the original.
+//!
+//! ## Wheel
+//! `--sieve wheel` only stores numbers coprime to 2·3·5: 8 residues out of every 30, so ~3.75x less memory.
+//! `--sieve wheel-packed` puts those 8 residues in the bits of a single byte; one byte per 30 numbers.
+//!
+//! ## Threads
+//! `--threads N` splits `[min, max]` into rounds of N contiguous pieces, each segmented-sieved on its own
+//! `std::thread::scope` worker (as in `scratch_scope_thread.rs`) against a shared list of base primes.
+//! Pieces are merged back in order.  With `--time` you get wall time per phase and per thread.
+//!
+//! ## Show
+//! `--show` streams primes to stdout as they're found (`--format lines|csv|json|binary-u64`, default `lines`)
+//! and moves all other messages to stderr.  With the segmented sieve (default, and with `--threads`)
+//! memory stays bounded, so this works:
+//! `./util_prime-sieve.rs 1000000000 --show --format lines | wc -l`
+//!
+//! ## Cache
+//! `--cache` (or `--cache=PATH`) keeps a wheel-packed prime bitmap on disk, in `data/no-sync/` by default.
+//! Queries within its bound are read straight from it (memory-mapped when possible);
+//! larger bounds only sieve the new stretch and append it.
+//! A header records layout, bound and an FNV-1a checksum; a cache that fails those checks is rebuilt.
+//!
+//! ## Count
+//! `count X` gives π(X), the number of primes `<= X`, without sieving or listing anything.
+//! (Lucy_Hedgehog's method: ~X^(3/4) time, ~sqrt(X) memory.  `count 1000000000000` is quick.)
+//!
+//! ## Single numbers
+//! - `is-prime N...`: Miller–Rabin, deterministic for all of u64 (and u128 below ~3.3·10^24).
+//! - `factor N...`: trial division by sieved small primes, then Pollard's rho (Brent) for u64/u128.
+//!
+//! Both read numbers one per line from stdin when none are given:
+//! `seq 1000000000000000000 1000000000000000010 | ./util_prime-sieve.rs factor`
+//!
+//! ## Analyze
+//! `analyze MAX [--min MIN] [--goldbach] [--json]` sieves the range and reports:
+//! maximal (record) gaps and where they occur, twin/cousin/sexy pair counts, a histogram of gap sizes,
+//! and optionally whether every even number in range is a sum of two primes in range.
+//!
 //! ## Convenience Section
 //!
 //! ### Shell Commands
@@ -31,10 +80,45 @@ owo-colors = "4.1"
 //! ### Links
 //! - [Cargo Book: Script](https://doc.rust-lang.org/nightly/cargo/reference/unstable.html#script)
 //! - [Github: Cargo-Script Tracking](https://github.com/rust-lang/cargo/issues/12207)
-use std::{error::Error, result::Result, time::Instant};
+use std::{collections::BTreeMap,
+          error::Error,
+          fs::{self, File},
+          io::{self, BufWriter, Read, Write},
+          num::NonZeroUsize,
+          path::{Path, PathBuf},
+          result::Result,
+          thread,
+          time::{Duration, Instant}};
 
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
+use memmap2::Mmap;
+use numeric_bounds::{Bounds, parse, parse_bounds};
 use owo_colors::OwoColorize;
+use prime_sieves::{PackedWheel, SEGMENT_SIZE, SegmentedPrimes, prime_sieve, segmented_prime_sieve, wheel_len, wheel_pos,
+                   wheel_prime_sieve, wheel_value};
+use serde::Serialize;
+
+/// Numbers each thread sieves per round of `--threads`.  (Bounds how many found primes wait to be merged.)
+const THREAD_PIECE_SIZE: usize = 32 * SEGMENT_SIZE;
+/// Factors below this are found by trial division (against sieved primes) before reaching for Pollard's rho.
+const TRIAL_DIVISION_LIMIT: usize = 1 << 16;
+/// Miller–Rabin bases that are deterministic for every u64.  (Jim Sinclair's set.)
+const MR_BASES_U64: [u128; 7] = [2, 325, 9_375, 28_178, 450_775, 9_780_504, 1_795_265_022];
+/// Miller–Rabin bases: the first 13 primes, deterministic below `MR_DETERMINISTIC_BOUND`.
+/// Above that, results are "probable prime".
+const MR_BASES_U128: [u128; 13] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41];
+/// Smallest strong pseudoprime to all of `MR_BASES_U128`.
+const MR_DETERMINISTIC_BOUND: u128 = 3_317_044_064_679_887_385_961_981;
+/// Where `--cache` keeps its bitmap unless given a path.  (`data/no-sync/` at the repo root.)
+const DEFAULT_CACHE_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../data/no-sync/prime-sieve.cache");
+/// First 8 bytes of a cache file.  Bump the version when the file format changes.
+const CACHE_MAGIC: [u8; 8] = *b"PRIMSV\x00\x01";
+/// Cache body layout: `PackedWheel` bytes, one per 30 numbers.
+const CACHE_LAYOUT_WHEEL30: u64 = 1;
+/// magic, layout, bound, checksum
+const CACHE_HEADER_LEN: usize = 32;
+/// Goldbach failures listed individually; past this they're only counted.
+const GOLDBACH_EXAMPLES_KEPT: usize = 20;
 
 /// Very simple, almost hyper-'literal' eratosthenes-sieve.
 ///
@@ -43,100 +127,1022 @@ use owo_colors::OwoColorize;
 ///
 /// Mostly for simple play, but useful int hos ranges.
 #[derive(Parser, Debug)]
-#[command(version, about)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
 struct Args {
-        /// Calculate all primes until some number (inclusive)
-        primes_until: Option<usize>,
+        #[command(subcommand)]
+        command: Option<Command>,
+
+        /// Calculate all primes until some number (inclusive).  Or a range: `min..=max`
+        #[arg(value_parser = parse_bounds::<usize>)]
+        primes_until: Option<Bounds<usize>>,
 
         /// Only show primes above this number
-        #[arg(short='n', long="min")]
+        #[arg(short='n', long="min", value_parser = parse::<usize>)]
         primes_from: Option<usize>,
 
-        /// Show all primes found
+        /// Show all primes found (streamed to stdout; other messages move to stderr)
         #[arg(short, long)]
         show: bool,
-        /// Show timing of core prime calculation and reaping. (Ignoring UI and display times.)
+        /// Output format for `--show`
+        #[arg(short, long, value_enum, default_value_t)]
+        format:       OutputFormat,
+        /// Show timing of core prime calculation and reaping. (Ignoring UI times; includes writing with `--show`.)
         #[arg(short, long = "time")]
         time_calc:    bool,
+        /// Sieve implementation to use
+        #[arg(long, value_enum, default_value_t)]
+        sieve:        SieveKind,
+        /// Split the (segmented) sieve across this many scoped threads
+        #[arg(short = 'j', long)]
+        threads:      Option<NonZeroUsize>,
+        /// Answer from (and grow) an on-disk prime bitmap cache.  `--cache=PATH` to pick the file
+        #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = DEFAULT_CACHE_PATH,
+              conflicts_with_all = ["threads", "sieve"])]
+        cache:        Option<PathBuf>,
+}
+/// Modes other than listing sieved primes.
+#[derive(Subcommand, Debug)]
+enum Command {
+        /// Count primes without sieving: π(x) via Lucy_Hedgehog's method
+        Count {
+                /// Count all primes until some number (inclusive)
+                #[arg(value_parser = parse::<u64>)]
+                x:           u64,
+                /// Only count primes above this number
+                #[arg(short = 'n', long = "min", value_parser = parse::<u64>)]
+                primes_from: Option<u64>,
+                /// Show timing of the count
+                #[arg(short, long = "time")]
+                time_calc:   bool,
+        },
+        /// Miller–Rabin primality test (reads stdin, one per line, if no numbers given)
+        IsPrime {
+                /// Numbers to test (up to u128)
+                numbers: Vec<u128>,
+        },
+        /// Prime factorization via trial division & Pollard's rho (reads stdin, one per line, if no numbers given)
+        Factor {
+                /// Numbers to factor (up to u128)
+                numbers: Vec<u128>,
+        },
+        /// Gap, prime-pair and (optionally) Goldbach report over a sieved range
+        Analyze {
+                /// Analyze primes until some number (inclusive)
+                #[arg(value_parser = parse::<usize>)]
+                primes_until: usize,
+                /// Only analyze primes above this number
+                #[arg(short = 'n', long = "min", value_parser = parse::<usize>)]
+                primes_from:  Option<usize>,
+                /// Check that every even number in range is a sum of two primes in range
+                #[arg(short, long)]
+                goldbach:     bool,
+                /// Print the report as JSON instead of a table
+                #[arg(long)]
+                json:         bool,
+        },
+}
+/// How `--show` writes primes.
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+enum OutputFormat {
+        /// One prime per line
+        #[default]
+        Lines,
+        /// A `prime` header, then one prime per line
+        Csv,
+        /// A single JSON array
+        Json,
+        /// Raw little-endian u64s, 8 bytes per prime
+        BinaryU64,
+}
+/// Available sieve implementations.
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+enum SieveKind {
+        /// One flag per number, all the way up to `max`
+        Simple,
+        /// Base primes up to `isqrt(max)`, then `[min, max]` in cache-sized blocks
+        #[default]
+        Segmented,
+        /// Mod-30 wheel: one flag per number coprime to 2, 3 and 5
+        Wheel,
+        /// Mod-30 wheel, bit-packed: one byte per 30 numbers
+        WheelPacked,
 }
 fn main() -> Result<(), Box<dyn Error>> {
 const DEFAULT_PRIMES_TILL: usize = 12_345;
-let args = Args::parse();
+let mut args = Args::parse();
+if let Some(command) = args.command {
+        return run_command(command);
+}
+if let Some(Bounds { min: Some(min), .. }) = args.primes_until {
+        if args.primes_from.is_some() {
+                Err("Error: give a minimum with `--min` or as `min..=max`, not both.")?
+        }
+        args.primes_from = Some(min);
+}
+// with `--show` stdout carries only primes, so everything else goes to stderr
+let mut status: Box<dyn Write> = if args.show { Box::new(io::stderr()) } else { Box::new(io::stdout()) };
 let primes_from_or_default = args.primes_from.unwrap_or(0);
-let primes_till_or_default = match args.primes_until {
+let primes_till_or_default = match args.primes_until.map(|bounds| bounds.max) {
         None => {
-                println!(
+                writeln!(
+                        status,
                         "No `{}` input given, defaulting to : {}",
                         "primes_until".green(),
                         DEFAULT_PRIMES_TILL.cyan()
-                );
+                )?;
                 DEFAULT_PRIMES_TILL
         }
         Some(p) => {
-                println!("You requested primes up to: {}", p.blue());
+                writeln!(status, "You requested primes up to: {}", p.blue())?;
                 p
         }
 };
-println!(
+writeln!(
+        status,
         "Calculating primes from ({}..={})...",
         primes_from_or_default.blue(),
         primes_till_or_default.blue()
-);
+)?;
 if primes_from_or_default > primes_till_or_default {
         Err("Error: your minimum is larger than your maximum.  Cancelling search.")?
 };
+if args.threads.is_some() && !matches!(args.sieve, SieveKind::Segmented) {
+        Err("Error: `--threads` only applies to `--sieve segmented`.")?
+};
 let start_time = Instant::now();
-let found_primes = prime_sieve(args.primes_from, primes_till_or_default);
+let sink = PrimeWriter::new(BufWriter::new(io::stdout().lock()), args.show.then_some(args.format));
+let (found_count, threaded_timing) = match sieve_into(&args, primes_till_or_default, sink, &mut status) {
+        // reader hung up (e.g. `| head`), nothing left to say
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+        result => result?,
+};
 let finish_duration = start_time.elapsed();
-println!(
+writeln!(
+        status,
         "Number of primes found <= {}: {}",
         primes_till_or_default.blue(),
-        found_primes.len().green().bold()
-);
-println!(
+        found_count.green().bold()
+)?;
+writeln!(
+        status,
         "which makes the range ({}..={}) {:.1}% prime.",
         primes_from_or_default.blue(),
         primes_till_or_default.blue(),
-        (100. * (found_primes.len() as f32)
+        (100. * (found_count as f32)
                 / ((primes_till_or_default - primes_from_or_default) as f32 + 2.))
                 .cyan()
                 .bold()
-);
-if args.show {
-        println!("{:?}", found_primes.magenta());
-}
+)?;
 if args.time_calc {
-        println!("Time taken: {:?}", finish_duration.red());
+        writeln!(status, "Time taken: {:?}", finish_duration.red())?;
+        if let Some(timing) = threaded_timing {
+                writeln!(status, "  base primes: {:?}", timing.base_primes.red())?;
+                writeln!(status, "  sieving:     {:?}", timing.sieving.red())?;
+                for (i, duration) in timing.per_thread.iter().enumerate() {
+                        writeln!(status, "    thread {:>2}: {:?}", i.cyan(), duration.red())?;
+                }
+                writeln!(status, "  merge:       {:?}", timing.merge.red())?;
+        }
 }
 Ok(())
 }
 
-/// I'll be surprised if this works efficiently as a mechanical, literal, procedure.
-fn prime_sieve(min: Option<usize>, max: usize) -> Vec<usize> {
-        // buncha default yes's
-        let mut primes = vec![true; max + 1];
-        primes[0] = false;
-        primes[1] = false;
-        // no need to go past sqrt(n).floor()
-        for i in  2..=max.isqrt() {
-                // skip if index was marked as multiple of preceding num
-                if primes[i] {
-                        // first value that's not been sieved would require p >= us, which would be us
-                        let mut index = i * i;
-                        // false for al p * n indices
-                        while index <= max {
-                                primes[index] = false;
-                                index += i;
+/// Runs the chosen sieve (or cache) over the requested range, feeding primes to `sink` in order.
+/// Returns the number of primes found, and phase timings if threaded.
+fn sieve_into<W: Write>(
+        args: &Args,
+        max: usize,
+        mut sink: PrimeWriter<W>,
+        status: &mut dyn Write,
+) -> io::Result<(usize, Option<ThreadedTiming>)> {
+        let min = args.primes_from;
+        if let Some(path) = &args.cache {
+                let cache = PrimeCache::ensure(path, max, status)?;
+                for p in cache.primes(min.unwrap_or(0), max) {
+                        sink.write(p)?;
+                }
+                return Ok((sink.finish()?, None));
+        }
+        let timing = match args.threads {
+                Some(threads) => Some(threaded_prime_sieve(min, max, threads, |primes| sink.write_all(primes))?),
+                None => {
+                        let primes: Box<dyn Iterator<Item = usize>> = match args.sieve {
+                                SieveKind::Simple => Box::new(prime_sieve(min, max).into_iter()),
+                                SieveKind::Segmented => Box::new(SegmentedPrimes::new(min.unwrap_or(0), max)),
+                                SieveKind::Wheel => Box::new(wheel_prime_sieve::<Vec<bool>>(min, max).into_iter()),
+                                SieveKind::WheelPacked => Box::new(wheel_prime_sieve::<PackedWheel>(min, max).into_iter()),
+                        };
+                        for p in primes {
+                                sink.write(p)?;
+                        }
+                        None
+                }
+        };
+        Ok((sink.finish()?, timing))
+}
+
+/// Counts primes as they arrive, and writes them out if given a format.
+struct PrimeWriter<W: Write> {
+        out:    W,
+        format: Option<OutputFormat>,
+        count:  usize,
+}
+impl<W: Write> PrimeWriter<W> {
+        fn new(out: W, format: Option<OutputFormat>) -> Self {
+                Self { out, format, count: 0 }
+        }
+        fn write(&mut self, p: usize) -> io::Result<()> {
+                match self.format {
+                        | None => {}
+                        | Some(OutputFormat::Lines) => writeln!(self.out, "{p}")?,
+                        | Some(OutputFormat::Csv) => {
+                                if self.count == 0 {
+                                        writeln!(self.out, "prime")?;
+                                }
+                                writeln!(self.out, "{p}")?
+                        }
+                        | Some(OutputFormat::Json) => {
+                                let separator = if self.count == 0 { "[" } else { "," };
+                                write!(self.out, "{separator}{p}")?
+                        }
+                        | Some(OutputFormat::BinaryU64) => self.out.write_all(&(p as u64).to_le_bytes())?,
+                }
+                self.count += 1;
+                Ok(())
+        }
+        fn write_all(&mut self, primes: &[usize]) -> io::Result<()> {
+                primes.iter().try_for_each(|&p| self.write(p))
+        }
+        /// Closes out the format and flushes.  Returns how many primes went through.
+        fn finish(mut self) -> io::Result<usize> {
+                match self.format {
+                        | Some(OutputFormat::Csv) if self.count == 0 => writeln!(self.out, "prime")?,
+                        | Some(OutputFormat::Json) if self.count == 0 => writeln!(self.out, "[]")?,
+                        | Some(OutputFormat::Json) => writeln!(self.out, "]")?,
+                        | _ => {}
+                }
+                self.out.flush()?;
+                Ok(self.count)
+        }
+}
+
+/// Dispatch for the non-sieving subcommands.
+fn run_command(command: Command) -> Result<(), Box<dyn Error>> {
+        match command {
+                | Command::Count { x, primes_from, time_calc } => {
+                        let min = primes_from.unwrap_or(0);
+                        if min > x {
+                                Err("Error: your minimum is larger than your maximum.  Cancelling count.")?
+                        }
+                        let start_time = Instant::now();
+                        let count = prime_count(x) - prime_count(min.saturating_sub(1));
+                        let finish_duration = start_time.elapsed();
+                        println!(
+                                "Number of primes in ({}..={}): {}",
+                                min.blue(),
+                                x.blue(),
+                                count.green().bold()
+                        );
+                        if time_calc {
+                                println!("Time taken: {:?}", finish_duration.red());
+                        }
+                }
+                | Command::IsPrime { numbers } => {
+                        for n in numbers_or_stdin(numbers) {
+                                let n = n?;
+                                if n < 2 {
+                                        println!("{}: {}", n.blue(), "not prime".red());
+                                } else if !is_prime(n) {
+                                        println!("{}: {}", n.blue(), "composite".red());
+                                } else if n < MR_DETERMINISTIC_BOUND {
+                                        println!("{}: {}", n.blue(), "prime".green());
+                                } else {
+                                        println!("{}: {}", n.blue(), "probable prime".yellow());
+                                }
+                        }
+                }
+                | Command::Factor { numbers } => {
+                        let small_primes = prime_sieve(None, TRIAL_DIVISION_LIMIT);
+                        for n in numbers_or_stdin(numbers) {
+                                let n = n?;
+                                let factors: Vec<_> = factorize(n, &small_primes).iter().map(u128::to_string).collect();
+                                println!("{}: {}", n.blue(), factors.join(" ").green());
+                        }
+                }
+                | Command::Analyze { primes_until, primes_from, goldbach, json } => {
+                        let min = primes_from.unwrap_or(0);
+                        if min > primes_until {
+                                Err("Error: your minimum is larger than your maximum.  Cancelling analysis.")?
+                        }
+                        let primes = segmented_prime_sieve(primes_from, primes_until);
+                        let analysis = analyze(&primes, min, primes_until, goldbach);
+                        if json {
+                                println!("{}", serde_json::to_string_pretty(&analysis)?);
+                        } else {
+                                print!("{analysis}");
+                        }
+                }
+        }
+        Ok(())
+}
+
+/// The given numbers, or else stdin read one number per line.  (Blank lines skipped.)
+fn numbers_or_stdin(numbers: Vec<u128>) -> Box<dyn Iterator<Item = Result<u128, Box<dyn Error>>>> {
+        if !numbers.is_empty() {
+                return Box::new(numbers.into_iter().map(Ok));
+        }
+        Box::new(io::stdin().lines().filter_map(|line| match line {
+                | Err(e) => Some(Err(e.into())),
+                | Ok(line) if line.trim().is_empty() => None,
+                | Ok(line) => Some(
+                        line.trim()
+                                .parse()
+                                .map_err(|e| format!("Error: couldn't read {:?} as a number: {e}", line.trim()).into()),
+                ),
+        }))
+}
+
+/// Wall times for the phases of `threaded_prime_sieve`.
+struct ThreadedTiming {
+        base_primes: Duration,
+        /// From first spawn to last join, summed over rounds.
+        sieving:     Duration,
+        /// Each thread's own sieving time, summed over rounds.
+        per_thread:  Vec<Duration>,
+        merge:       Duration,
+}
+
+/// Segmented sieve with `[min, max]` split into rounds of contiguous pieces, one scoped thread per piece.
+/// After each round the pieces' primes are handed to `emit` in range order.
+fn threaded_prime_sieve(
+        min: Option<usize>,
+        max: usize,
+        threads: NonZeroUsize,
+        mut emit: impl FnMut(&[usize]) -> io::Result<()>,
+) -> io::Result<ThreadedTiming> {
+        let start = Instant::now();
+        let base_primes = prime_sieve(None, max.isqrt());
+        let base_primes_time = start.elapsed();
+
+        let mut per_thread = vec![Duration::ZERO; threads.get()];
+        let (mut sieving, mut merge) = (Duration::ZERO, Duration::ZERO);
+        let round_len = THREAD_PIECE_SIZE.saturating_mul(threads.get());
+        for round_low in (min.unwrap_or(0)..=max).step_by(round_len) {
+                let round_high = max.min(round_low.saturating_add(round_len - 1));
+                // ceil(len / threads), without overflowing len at usize::MAX
+                let piece_len = (round_high - round_low) / threads + 1;
+                let pieces = (round_low..=round_high).step_by(piece_len);
+
+                let start = Instant::now();
+                let results: Vec<_> = thread::scope(|s| {
+                        let handles: Vec<_> = pieces
+                                .map(|low| {
+                                        let base_primes = &base_primes;
+                                        s.spawn(move || {
+                                                let start = Instant::now();
+                                                let high = round_high.min(low + (piece_len - 1));
+                                                let primes: Vec<_> =
+                                                        SegmentedPrimes::with_base_primes(base_primes, low, high).collect();
+                                                (primes, start.elapsed())
+                                        })
+                                })
+                                .collect();
+                        // joining in spawn order keeps the pieces in range order
+                        handles.into_iter().map(|h| h.join().expect("sieve thread panicked")).collect()
+                });
+                sieving += start.elapsed();
+
+                let start = Instant::now();
+                for (thread_time, (primes, duration)) in per_thread.iter_mut().zip(results) {
+                        *thread_time += duration;
+                        emit(&primes)?;
+                }
+                merge += start.elapsed();
+        }
+        Ok(ThreadedTiming {
+                base_primes: base_primes_time,
+                sieving,
+                per_thread,
+                merge,
+        })
+}
+
+/// Wheel-packed prime bitmap persisted on disk.  See `CACHE_*` for the header.
+/// The bound always ends a wheel turn (`30k + 29`), so every byte of the body is complete.
+struct PrimeCache {
+        /// Largest number covered (inclusive).
+        bound: usize,
+        body:  CacheBody,
+}
+/// Cache body: the mapped file when possible, read into memory otherwise (or when freshly built).
+enum CacheBody {
+        /// Whole file, header included.
+        Mapped(Mmap),
+        /// Bitmap only.
+        Owned(Vec<u8>),
+}
+impl PrimeCache {
+        /// Opens the cache at `path`, first building, extending or rebuilding it so it covers `max`.
+        fn ensure(path: &Path, max: usize, status: &mut dyn Write) -> io::Result<Self> {
+                let (mut bitmap, why) = match Self::load(path) {
+                        | Ok(cache) if cache.bound >= max => {
+                                writeln!(status, "Using cache {} (covers up to {})", path.display(), cache.bound.blue())?;
+                                return Ok(cache);
+                        }
+                        | Ok(cache) => (cache.bitmap().to_vec(), format!("extending from {}", cache.bound)),
+                        | Err(why) => (vec![], format!("rebuilding: {why}")),
+                };
+                let bound = (max / 30).saturating_mul(30).saturating_add(29);
+                writeln!(status, "Cache {} {} to {}...", path.display(), why.yellow(), bound.blue())?;
+                extend_wheel_bitmap(&mut bitmap, bound);
+                Self::store(path, bound, &bitmap)?;
+                Ok(Self {
+                        bound,
+                        body: CacheBody::Owned(bitmap),
+                })
+        }
+
+        /// Reads and checks a cache file.  `Err` says why it can't be used.
+        fn load(path: &Path) -> Result<Self, String> {
+                let mut file = match File::open(path) {
+                        | Ok(file) => file,
+                        | Err(e) if e.kind() == io::ErrorKind::NotFound => Err("no cache yet")?,
+                        | Err(e) => Err(format!("can't open: {e}"))?,
+                };
+                let mut header = [0; CACHE_HEADER_LEN];
+                file.read_exact(&mut header).map_err(|_| "corrupt: truncated header")?;
+                let field = |i: usize| u64::from_le_bytes(header[8 * i..8 * (i + 1)].try_into().unwrap());
+                if header[..8] != CACHE_MAGIC {
+                        Err("stale: not a cache file, or an older format")?
+                }
+                if field(1) != CACHE_LAYOUT_WHEEL30 {
+                        Err(format!("stale: layout {} is not {CACHE_LAYOUT_WHEEL30}", field(1)))?
+                }
+                let bound = usize::try_from(field(2)).map_err(|_| "corrupt: bound too large")?;
+                if (bound + 1) % 30 != 0 {
+                        Err(format!("corrupt: bound {bound} doesn't end a wheel turn"))?
+                }
+                // SAFETY: the map is only read, and only this script writes the file (via rename, never in place).
+                let body = match unsafe { Mmap::map(&file) } {
+                        | Ok(map) => CacheBody::Mapped(map),
+                        | Err(_) => {
+                                let mut bitmap = vec![];
+                                file.read_to_end(&mut bitmap).map_err(|e| format!("can't read: {e}"))?;
+                                CacheBody::Owned(bitmap)
+                        }
+                };
+                let cache = Self { bound, body };
+                if cache.bitmap().len() != (bound + 1) / 30 {
+                        Err(format!("corrupt: body is {} bytes, expected {}", cache.bitmap().len(), (bound + 1) / 30))?
+                }
+                if fnv1a(cache.bitmap()) != field(3) {
+                        Err("corrupt: checksum mismatch")?
+                }
+                Ok(cache)
+        }
+
+        /// Writes header + bitmap next to `path`, then renames it into place.
+        fn store(path: &Path, bound: usize, bitmap: &[u8]) -> io::Result<()> {
+                if let Some(dir) = path.parent() {
+                        fs::create_dir_all(dir)?;
+                }
+                let tmp_path = path.with_extension("tmp");
+                let mut out = BufWriter::new(File::create(&tmp_path)?);
+                out.write_all(&CACHE_MAGIC)?;
+                for field in [CACHE_LAYOUT_WHEEL30, bound as u64, fnv1a(bitmap)] {
+                        out.write_all(&field.to_le_bytes())?;
+                }
+                out.write_all(bitmap)?;
+                out.into_inner()?.sync_all()?;
+                fs::rename(tmp_path, path)
+        }
+
+        fn bitmap(&self) -> &[u8] {
+                match &self.body {
+                        | CacheBody::Mapped(map) => &map[CACHE_HEADER_LEN..],
+                        | CacheBody::Owned(bitmap) => bitmap,
+                }
+        }
+
+        /// Primes in `[min, max]`, for `max <= bound`.
+        fn primes(&self, min: usize, max: usize) -> impl Iterator<Item = usize> + '_ {
+                let bitmap = self.bitmap();
+                let positions = (if min == 0 { 0 } else { wheel_len(min - 1) })..wheel_len(max);
+                // a byte at a time, popping set bits off with `trailing_zeros`
+                let set_positions = (positions.start / 8..positions.end.div_ceil(8)).flat_map(move |k| {
+                        let mut bits = bitmap[k];
+                        std::iter::from_fn(move || {
+                                (bits != 0).then(|| {
+                                        let j = bits.trailing_zeros() as usize;
+                                        bits &= bits - 1;
+                                        8 * k + j
+                                })
+                        })
+                });
+                [2, 3, 5]
+                        .into_iter()
+                        .filter(move |&p| min <= p && p <= max)
+                        .chain(set_positions.filter(move |pos| positions.contains(pos)).map(wheel_value))
+        }
+}
+
+/// Grows a `PackedWheel`-style bitmap to cover `bound` (which must end a wheel turn),
+/// sieving only the numbers it didn't already cover.
+fn extend_wheel_bitmap(bitmap: &mut Vec<u8>, bound: usize) {
+        let from = 30 * bitmap.len();
+        bitmap.resize((bound + 1) / 30, 0);
+        // everything from 7 up is coprime to 30, so has a wheel bit
+        for p in SegmentedPrimes::new(from.max(7), bound) {
+                let pos = wheel_pos(p);
+                bitmap[pos / 8] |= 1 << (pos % 8);
+        }
+}
+
+/// 64-bit FNV-1a.  Plenty to catch a torn or bit-flipped cache.
+fn fnv1a(bytes: &[u8]) -> u64 {
+        bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// π(x): count of primes `<= x`, via Lucy_Hedgehog's method.
+///
+/// Let S(v) count the numbers in `2..=v` that survive sieving by every prime below p.
+/// Only the ~2·sqrt(x) distinct values of `x / i` ever matter, so S is kept for those alone:
+/// `small[v]` for `v <= isqrt(x)` and `large[i] = S(x / i)` for the rest.
+/// Sieving by a prime p removes the survivors whose smallest factor is p:
+/// S(v) -= S(v / p) - S(p - 1), for every tracked v >= p².
+fn prime_count(x: u64) -> u64 {
+        if x < 2 {
+                return 0;
+        }
+        let r = x.isqrt() as usize;
+        let mut small: Vec<u64> = (0..=r as u64).map(|v| v.saturating_sub(1)).collect();
+        let mut large: Vec<u64> = (0..=r as u64).map(|i| x.checked_div(i).map_or(0, |q| q - 1)).collect();
+        for p in 2..=r {
+                // p is prime iff sieving by smaller primes left it standing
+                if small[p] == small[p - 1] {
+                        continue;
+                }
+                let below_p = small[p - 1];
+                let p2 = (p * p) as u64;
+                // large first, since it reads `small` values from before this round
+                for i in 1..=r.min((x / p2) as usize) {
+                        let d = i * p;
+                        let s_div = if d <= r { large[d] } else { small[(x / d as u64) as usize] };
+                        large[i] -= s_div - below_p;
+                }
+                // descending, so `small[v / p]` is still from before this round
+                for v in (p * p..=r).rev() {
+                        small[v] -= small[v / p] - below_p;
+                }
+        }
+        large[1]
+}
+
+/// Number-theory report over the primes of `[min, max]`.
+#[derive(Serialize, Debug)]
+struct Analysis {
+        min:           usize,
+        max:           usize,
+        prime_count:   usize,
+        /// Each gap larger than every gap before it in range.
+        maximal_gaps:  Vec<Gap>,
+        pairs:         PairCounts,
+        /// gap size -> how often it occurs between consecutive primes
+        gap_histogram: BTreeMap<usize, usize>,
+        goldbach:      Option<Goldbach>,
+}
+#[derive(Serialize, Debug, PartialEq)]
+struct Gap {
+        size:  usize,
+        /// prime before the gap
+        after: usize,
+        /// prime after the gap
+        until: usize,
+}
+/// Counts of `(p, p + k)` with both prime and in range.  (Not necessarily consecutive primes.)
+#[derive(Serialize, Debug, PartialEq)]
+struct PairCounts {
+        twin:   usize,
+        cousin: usize,
+        sexy:   usize,
+}
+#[derive(Serialize, Debug)]
+struct Goldbach {
+        evens_checked:    usize,
+        /// evens that are *not* a sum of two primes in range
+        failure_count:    usize,
+        /// the first `GOLDBACH_EXAMPLES_KEPT` failures
+        failure_examples: Vec<usize>,
+}
+
+/// `primes` must be exactly the (sorted) primes of `[min, max]`.
+fn analyze(primes: &[usize], min: usize, max: usize, goldbach: bool) -> Analysis {
+        let mut maximal_gaps: Vec<Gap> = vec![];
+        let mut gap_histogram = BTreeMap::new();
+        for pair in primes.windows(2) {
+                let (after, until) = (pair[0], pair[1]);
+                let size = until - after;
+                *gap_histogram.entry(size).or_insert(0) += 1;
+                if maximal_gaps.last().is_none_or(|record| size > record.size) {
+                        maximal_gaps.push(Gap { size, after, until });
+                }
+        }
+        let pairs_at = |k: usize| primes.iter().filter(|&&p| primes.binary_search(&(p + k)).is_ok()).count();
+        let pairs = PairCounts {
+                twin:   pairs_at(2),
+                cousin: pairs_at(4),
+                sexy:   pairs_at(6),
+        };
+        let goldbach = goldbach.then(|| check_goldbach(primes, min, max));
+        Analysis {
+                min,
+                max,
+                prime_count: primes.len(),
+                maximal_gaps,
+                pairs,
+                gap_histogram,
+                goldbach,
+        }
+}
+
+/// Checks every even `n` in `[max(min, 4), max]` for primes `p + q = n` with both `p, q` in `primes`.
+fn check_goldbach(primes: &[usize], min: usize, max: usize) -> Goldbach {
+        let lowest = min.max(4);
+        let offset = primes.first().copied().unwrap_or(0);
+        let mut is_prime_in_range = vec![false; primes.last().map_or(0, |&last| last - offset + 1)];
+        for &p in primes {
+                is_prime_in_range[p - offset] = true;
+        }
+        let in_range = |q: usize| q.checked_sub(offset).and_then(|i| is_prime_in_range.get(i)) == Some(&true);
+        let mut goldbach = Goldbach {
+                evens_checked:    0,
+                failure_count:    0,
+                failure_examples: vec![],
+        };
+        for n in (lowest.next_multiple_of(2)..=max).step_by(2) {
+                goldbach.evens_checked += 1;
+                let found = primes.iter().take_while(|&&p| p <= n / 2).any(|&p| in_range(n - p));
+                if !found {
+                        goldbach.failure_count += 1;
+                        if goldbach.failure_examples.len() < GOLDBACH_EXAMPLES_KEPT {
+                                goldbach.failure_examples.push(n);
                         }
                 }
         }
-        let min = min.unwrap_or(0);
-        // collect unsieved bits
-        let mut result = vec![];
-        for (i, b) in primes.iter().enumerate().skip(min) {
-                if *b {
-                        result.push(i);
+        goldbach
+}
+
+impl std::fmt::Display for Analysis {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                writeln!(
+                        f,
+                        "Primes in ({}..={}): {}",
+                        self.min.blue(),
+                        self.max.blue(),
+                        self.prime_count.green().bold()
+                )?;
+                writeln!(f, "{}", "Maximal gaps:".cyan())?;
+                writeln!(f, "  {:>8} {:>20} {:>20}", "gap", "after", "until")?;
+                for gap in &self.maximal_gaps {
+                        writeln!(f, "  {:>8} {:>20} {:>20}", gap.size.green(), gap.after, gap.until)?;
+                }
+                writeln!(f, "{}", "Prime pairs:".cyan())?;
+                writeln!(f, "  {:<16} {:>12}", "twin (p, p+2)", self.pairs.twin.green())?;
+                writeln!(f, "  {:<16} {:>12}", "cousin (p, p+4)", self.pairs.cousin.green())?;
+                writeln!(f, "  {:<16} {:>12}", "sexy (p, p+6)", self.pairs.sexy.green())?;
+                writeln!(f, "{}", "Gap histogram:".cyan())?;
+                writeln!(f, "  {:>8} {:>12}", "gap", "count")?;
+                for (size, count) in &self.gap_histogram {
+                        writeln!(f, "  {:>8} {:>12}", size, count.green())?;
                 }
+                if let Some(goldbach) = &self.goldbach {
+                        writeln!(f, "{}", "Goldbach:".cyan())?;
+                        if goldbach.failure_count == 0 {
+                                writeln!(
+                                        f,
+                                        "  all {} even numbers in range are a sum of two primes in range",
+                                        goldbach.evens_checked.green()
+                                )?;
+                        } else {
+                                writeln!(
+                                        f,
+                                        "  {} of {} even numbers in range are not a sum of two primes in range, e.g. {:?}",
+                                        goldbach.failure_count.red(),
+                                        goldbach.evens_checked,
+                                        goldbach.failure_examples
+                                )?;
+                        }
+                }
+                Ok(())
+        }
+}
+
+/// `(a + b) % m`, for `a, b < m`, without overflowing.
+fn add_mod(a: u128, b: u128, m: u128) -> u128 {
+        let (sum, overflowed) = a.overflowing_add(b);
+        if overflowed || sum >= m { sum.wrapping_sub(m) } else { sum }
+}
+/// `(a * b) % m`, for `a, b < m`.
+/// Direct when `m` fits in 64 bits; otherwise double-and-add so nothing needs 256 bits.
+fn mul_mod(a: u128, b: u128, m: u128) -> u128 {
+        if m <= u64::MAX as u128 {
+                return a * b % m;
+        }
+        let (mut a, mut b, mut product) = (a, b, 0);
+        while b > 0 {
+                if b & 1 == 1 {
+                        product = add_mod(product, a, m);
+                }
+                a = add_mod(a, a, m);
+                b >>= 1;
+        }
+        product
+}
+/// `base^exp % m`
+fn pow_mod(base: u128, mut exp: u128, m: u128) -> u128 {
+        let (mut base, mut result) = (base % m, 1 % m);
+        while exp > 0 {
+                if exp & 1 == 1 {
+                        result = mul_mod(result, base, m);
+                }
+                base = mul_mod(base, base, m);
+                exp >>= 1;
         }
         result
 }
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+        while b != 0 {
+                (a, b) = (b, a % b);
+        }
+        a
+}
+
+/// Miller–Rabin.  Exact for `n < MR_DETERMINISTIC_BOUND` (so all of u64); "probably prime" above that.
+fn is_prime(n: u128) -> bool {
+        for p in MR_BASES_U128 {
+                if n.is_multiple_of(p) {
+                        return n == p;
+                }
+        }
+        if n < 2 {
+                return false;
+        }
+        // n - 1 = d · 2^s, with d odd
+        let s = (n - 1).trailing_zeros();
+        let d = (n - 1) >> s;
+        let bases: &[u128] = if n <= u64::MAX as u128 { &MR_BASES_U64 } else { &MR_BASES_U128 };
+        'bases: for &a in bases {
+                let a = a % n;
+                if a == 0 {
+                        continue;
+                }
+                let mut x = pow_mod(a, d, n);
+                if x == 1 || x == n - 1 {
+                        continue;
+                }
+                for _ in 1..s {
+                        x = mul_mod(x, x, n);
+                        if x == n - 1 {
+                                continue 'bases;
+                        }
+                }
+                // `a` witnesses that n is composite
+                return false;
+        }
+        true
+}
+
+/// A non-trivial factor of an odd composite `n`: Pollard's rho with Brent's cycle detection.
+/// (Batches `|x - y|` products so only every `BATCH`th step needs a gcd.)
+fn pollard_brent(n: u128) -> u128 {
+        const BATCH: usize = 128;
+        for c in 1.. {
+                let f = |x: u128| add_mod(mul_mod(x, x, n), c, n);
+                let (mut x, mut y, mut ys) = (2, 2, 2);
+                let (mut g, mut q, mut r) = (1, 1, 1);
+                while g == 1 {
+                        x = y;
+                        for _ in 0..r {
+                                y = f(y);
+                        }
+                        let mut k = 0;
+                        while k < r && g == 1 {
+                                ys = y;
+                                for _ in 0..BATCH.min(r - k) {
+                                        y = f(y);
+                                        q = mul_mod(q, x.abs_diff(y), n);
+                                }
+                                g = gcd(q, n);
+                                k += BATCH;
+                        }
+                        r *= 2;
+                }
+                if g == n {
+                        // the batch overshot; redo it one step at a time
+                        loop {
+                                ys = f(ys);
+                                g = gcd(x.abs_diff(ys), n);
+                                if g > 1 {
+                                        break;
+                                }
+                        }
+                }
+                // g == n means this `c` cycled without splitting n; try another
+                if g != n {
+                        return g;
+                }
+        }
+        unreachable!("ran out of rho constants")
+}
+
+/// Prime factors of `n`, ascending, with repeats.  (`small_primes` should be sorted and start at 2.)
+/// Like coreutils `factor`, 0 and 1 have none.
+fn factorize(mut n: u128, small_primes: &[usize]) -> Vec<u128> {
+        if n < 2 {
+                return vec![];
+        }
+        let mut factors = vec![];
+        for p in small_primes.iter().map(|&p| p as u128) {
+                if p * p > n {
+                        break;
+                }
+                while n.is_multiple_of(p) {
+                        factors.push(p);
+                        n /= p;
+                }
+        }
+        let mut unsplit = vec![n];
+        while let Some(m) = unsplit.pop() {
+                if m == 1 {
+                        continue;
+                }
+                if is_prime(m) {
+                        factors.push(m);
+                } else {
+                        let d = pollard_brent(m);
+                        unsplit.extend([d, m / d]);
+                }
+        }
+        factors.sort_unstable();
+        factors
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        /// Collects what `threaded_prime_sieve` emits.
+        fn threaded_collect(min: Option<usize>, max: usize, threads: usize) -> Vec<usize> {
+                let mut primes = vec![];
+                threaded_prime_sieve(min, max, NonZeroUsize::new(threads).unwrap(), |found| {
+                        primes.extend_from_slice(found);
+                        Ok(())
+                })
+                .unwrap();
+                primes
+        }
+
+        #[test]
+        fn threaded_matches_simple() {
+                let max = 2 * SEGMENT_SIZE + 101;
+                for threads in [1, 2, 3, 7, 64] {
+                        for min in [None, Some(5), Some(SEGMENT_SIZE + 1), Some(max - 3), Some(max)] {
+                                let primes = threaded_collect(min, max, threads);
+                                assert_eq!(primes, prime_sieve(min, max), "{threads} threads, min: {min:?}");
+                        }
+                }
+                // more than one round
+                let max = THREAD_PIECE_SIZE + 1_000;
+                assert_eq!(threaded_collect(Some(7), max, 1), segmented_prime_sieve(Some(7), max));
+        }
+
+        #[test]
+        fn prime_writer_formats() {
+                let written = |format, primes: &[usize]| {
+                        let mut out = vec![];
+                        let mut writer = PrimeWriter::new(&mut out, Some(format));
+                        writer.write_all(primes).unwrap();
+                        assert_eq!(writer.finish().unwrap(), primes.len());
+                        out
+                };
+                assert_eq!(written(OutputFormat::Lines, &[2, 3, 5]), b"2\n3\n5\n");
+                assert_eq!(written(OutputFormat::Csv, &[2, 3]), b"prime\n2\n3\n");
+                assert_eq!(written(OutputFormat::Csv, &[]), b"prime\n");
+                assert_eq!(written(OutputFormat::Json, &[2, 3, 5]), b"[2,3,5]\n");
+                assert_eq!(written(OutputFormat::Json, &[]), b"[]\n");
+                assert_eq!(written(OutputFormat::BinaryU64, &[2, 257]), [2, 0, 0, 0, 0, 0, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0]);
+        }
+
+        #[test]
+        fn prime_count_powers_of_ten() {
+                const PI_POW_10: [u64; 13] = [
+                        0,
+                        4,
+                        25,
+                        168,
+                        1_229,
+                        9_592,
+                        78_498,
+                        664_579,
+                        5_761_455,
+                        50_847_534,
+                        455_052_511,
+                        4_118_054_813,
+                        37_607_912_018,
+                ];
+                for (k, expected) in PI_POW_10.into_iter().enumerate() {
+                        assert_eq!(prime_count(10_u64.pow(k as u32)), expected, "π(10^{k})");
+                }
+        }
+
+        #[test]
+        fn prime_count_matches_sieve() {
+                for k in 0..=6 {
+                        let x = 10_usize.pow(k);
+                        assert_eq!(prime_count(x as u64), prime_sieve(None, x).len() as u64, "π(10^{k})");
+                }
+                let primes = prime_sieve(None, 10_000);
+                for x in (0..2_000).chain([9_973, 9_999, 10_000]) {
+                        let expected = primes.iter().take_while(|&&p| p <= x).count() as u64;
+                        assert_eq!(prime_count(x as u64), expected, "π({x})");
+                }
+        }
+
+        #[test]
+        fn is_prime_matches_sieve() {
+                let primes = prime_sieve(None, 100_000);
+                let from_mr: Vec<_> = (0..=100_000_u128).filter(|&n| is_prime(n)).map(|n| n as usize).collect();
+                assert_eq!(from_mr, primes);
+                // largest u64 prime; a strong pseudoprime to bases 2..=37; Mersenne primes 2^61-1, 2^127-1
+                assert!(is_prime(18_446_744_073_709_551_557));
+                assert!(!is_prime(318_665_857_834_031_151_167_461));
+                assert!(is_prime((1 << 61) - 1));
+                assert!(is_prime((1 << 127) - 1));
+        }
+
+        #[test]
+        fn factorize_round_trips() {
+                let small_primes = prime_sieve(None, TRIAL_DIVISION_LIMIT);
+                let cases: [(u128, &[u128]); 6] = [
+                        (0, &[]),
+                        (1, &[]),
+                        (360, &[2, 2, 2, 3, 3, 5]),
+                        (18_446_744_073_709_551_615, &[3, 5, 17, 257, 641, 65_537, 6_700_417]),
+                        // product of two ~2^31 primes, out of trial division's reach
+                        (4_611_686_014_132_420_609, &[2_147_483_647, 2_147_483_647]),
+                        (u128::MAX, &[3, 5, 17, 257, 641, 65_537, 274_177, 6_700_417, 67_280_421_310_721]),
+                ];
+                for (n, expected) in cases {
+                        assert_eq!(factorize(n, &small_primes), expected, "n: {n}");
+                }
+        }
+
+        #[test]
+        fn analyze_small_range() {
+                let primes = prime_sieve(None, 100);
+                let analysis = analyze(&primes, 0, 100, true);
+                assert_eq!(analysis.prime_count, 25);
+                let records: Vec<_> = analysis.maximal_gaps.iter().map(|g| (g.size, g.after)).collect();
+                assert_eq!(records, [(1, 2), (2, 3), (4, 7), (6, 23), (8, 89)]);
+                assert_eq!(analysis.pairs, PairCounts { twin: 8, cousin: 8, sexy: 15 });
+                assert_eq!(analysis.gap_histogram.values().sum::<usize>(), 24);
+                let goldbach = analysis.goldbach.unwrap();
+                assert_eq!((goldbach.evens_checked, goldbach.failure_count), (49, 0));
+
+                // summands must be in range too: nothing below 2·53 works
+                let primes = prime_sieve(Some(50), 120);
+                let goldbach = analyze(&primes, 50, 120, true).goldbach.unwrap();
+                assert_eq!(goldbach.failure_examples.first(), Some(&50));
+                assert!(!goldbach.failure_examples.contains(&106));
+        }
+
+        #[test]
+        fn cache_builds_extends_and_rebuilds() {
+                let path = std::env::temp_dir().join(format!("util_prime-sieve-test-{}.cache", std::process::id()));
+                let mut status = vec![];
+                let _ = fs::remove_file(&path);
+
+                let cache = PrimeCache::ensure(&path, 10_000, &mut status).unwrap();
+                assert_eq!(cache.bound, 10_019);
+                assert_eq!(cache.primes(0, 10_000).collect::<Vec<_>>(), prime_sieve(None, 10_000));
+                drop(cache);
+                let cache = PrimeCache::load(&path).unwrap();
+                assert!(matches!(cache.body, CacheBody::Mapped(_)));
+                assert_eq!(cache.primes(3, 1_000).collect::<Vec<_>>(), prime_sieve(Some(3), 1_000));
+                drop(cache);
+
+                let cache = PrimeCache::ensure(&path, 100_000, &mut status).unwrap();
+                assert_eq!(cache.primes(9_000, 100_000).collect::<Vec<_>>(), prime_sieve(Some(9_000), 100_000));
+                drop(cache);
+
+                let mut bytes = fs::read(&path).unwrap();
+                bytes[CACHE_HEADER_LEN + 100] ^= 0b100;
+                fs::write(&path, &bytes).unwrap();
+                assert_eq!(PrimeCache::load(&path).err().as_deref(), Some("corrupt: checksum mismatch"));
+                let cache = PrimeCache::ensure(&path, 50_000, &mut status).unwrap();
+                assert_eq!(cache.primes(0, 50_000).collect::<Vec<_>>(), prime_sieve(None, 50_000));
+                assert!(String::from_utf8_lossy(&status).contains("checksum mismatch"));
+
+                fs::remove_file(&path).unwrap();
+        }
+}
@@ -2,9 +2,19 @@
 ---
 package.edition = "2024"
 [dependencies]
+anstream = "0.6"
 clap = { version = "4.5", features = ["derive"] }
+csv = "1.3"
+glob = "0.3"
+jiff = "0.2"
+libc = "0.2"
 owo-colors = "4.1.0"
+regex = "1"
+serde = { version = "1", features = ["derive"] }
+serde_json = "1"
 walkdir = "2.5.0"
+[dev-dependencies]
+tempfile = "3"
 ---
 //! Various things, including
 //! looking at everything in path:
@@ -14,6 +24,59 @@ walkdir = "2.5.0"
 //! echo $PATH | sd : '\n' | xargs -I_ fd '.*' _ -t f | sort
 //! ```
 //!
+//! ## Scan
+//! By default only what `$PATH` lookup can actually run: executables (or symlinks to one) directly inside each entry.
+//! `--recursive` walks each entry fully and lists everything, directories included (the original behaviour).
+//! `--columns size,mtime,perms,target,broken` adds per-file columns.
+//! (Asking for `broken` also lists broken symlinks in the default scan; they'd be skipped as non-executable otherwise.)
+//!
+//! ## Formats (`--format json|ndjson|csv|tsv`)
+//! Found paths (default), raw paths (`-r`), and errors (`-s`, after the found paths) as records instead of text.
+//! One shape for all three: `kind` (`file`, `path`, `error`), `file`, `path`, `path_index`, `depth`, `error_kind`;
+//! fields that don't apply are empty/`null`.  e.g. `--format csv | xsv table`, `--format ndjson | jq -r .path`
+//!
+//! Colors are stripped whenever stdout isn't a terminal (all output goes through `anstream`).
+//!
+//! ## Sources (`--columns source`, `--group-by-source`)
+//! Which installer put an executable there, from where it lives (or, for symlinks, where it resolves):
+//! cargo (`$CARGO_HOME/bin`), rustup (toolchains, and the proxies in `~/.cargo/bin`),
+//! `/usr/local`, Homebrew, Nix profiles & store, `~/.local/bin`, pyenv & its shims, rye shims, conda, system dirs.
+//! Rustup proxies (`cargo`, `rustc`, ...) are copies, hard links, or symlinks of `rustup` itself,
+//! so they're recognized by size & content hash against it rather than by name.
+//! Shown in `--shadows`, as a column, as a `--format` field, and as grouping for the found-paths listing.
+//!
+//! ## Shadows (`--shadows`)
+//! Keeps the real `$PATH` order and groups executables directly inside each entry by name.
+//! For every name found more than once: the winner (what the shell runs) and each entry it shadows,
+//! with their `$PATH` precedence index.  (Entries that resolve to the winner's own file are marked `same file`.)
+//!
+//! ## Audit (`--audit`)
+//! Checks each `$PATH` entry, in order, for: missing or non-directory entries,
+//! duplicates (exact, or only after canonicalization), relative & empty entries (both mean "current directory"),
+//! group- or world-writable directories, and symlinked entries.
+//! Ends with a suggested `$PATH`: same order, first occurrence of each directory only,
+//! without missing, non-directory, relative, or empty entries.
+//!
+//! ## Which (`which <name|glob|/regex/>...`)
+//! Like `which -a`: every executable file (or symlink to one) directly inside a `$PATH` entry, in precedence order.
+//! Each pattern is an exact name, a glob if it has any of `*?[`, or a regex if wrapped in slashes: `'/^py(thon)?3?$/'`.
+//! Prints precedence index, path, symlink chain, and the final file's mode.
+//! Exits nonzero if any pattern matched nothing.
+//!
+//! ## Snapshot & Diff (`snapshot --out a.json`, `diff a.json [b.json]`)
+//! For "works in my shell, not in the hook": snapshot `$PATH` and its executables (the default scan) in each environment,
+//! then diff them.  Without a second file, diffs against the current environment.
+//! Shows added & removed `$PATH` directories, executables that appeared or disappeared,
+//! and executables whose winning location changed.
+//!
+//! ## Versions (`--versions`)
+//! Runs each winning executable with `--version` (`--version-arg` to change it), in parallel (`--jobs`).
+//! stdin is closed and output captured; anything still running after `--timeout` seconds is killed,
+//! along with its process group.  The group is killed after a normal exit too (background children),
+//! and output is only read until the deadline, so a daemon that keeps the pipes can't hold a probe up.  Reports the first version-looking line (`\d+\.\d+`) of stdout, then stderr.
+//! Pick what to run with `--only` (names/globs/regexes as in `which`) or `--deps-csv ../ad_deps.csv` (its `NAME` column).
+//! Probing *everything* on `$PATH` runs a few thousand unknown binaries, so it takes an explicit `--all`.
+//!
 //! ## Run:
 //! ```zsh
 //! clear; ./sort-path.rs
@@ -21,16 +84,37 @@ walkdir = "2.5.0"
 //!
 //! ## Convenience note:
 //! `chmod u+x sort-path.rs`
-use std::{collections::HashMap, env, fmt::{self, Display}, error::Error, path::{Path, PathBuf}, result::Result};
+use std::{collections::{BTreeMap, HashMap},
+          env,
+          hash::{DefaultHasher, Hasher as _},
+          error::Error,
+          fmt::{self, Display},
+          fs,
+          io::{self, Read, Write},
+          num::NonZeroUsize,
+          os::{fd::AsRawFd, unix::{fs::PermissionsExt as _, process::CommandExt as _}},
+          path::{Path, PathBuf},
+          process::{Command as Process, Stdio},
+          result::Result,
+          sync::{LazyLock, Mutex, atomic::{AtomicUsize, Ordering}},
+          thread,
+          time::{Duration, Instant}};
 
-use clap::Parser;
+// shadow std's: strips colors when stdout isn't a terminal
+use anstream::{print, println};
+use clap::{Parser, Subcommand, ValueEnum};
 use owo_colors::OwoColorize as _;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use walkdir::WalkDir;
 
 /// Sort-Path - Displays files findable via $PATH
 #[derive(Parser, Debug)]
-#[command(version, about)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
 struct Args {
+        #[command(subcommand)]
+        command: Option<Command>,
+
         /// Show the explicit $PATH paths (not files in them).
         #[arg(short, long)]
         raw_paths: bool,
@@ -42,17 +126,181 @@ struct Args {
         /// Only show found-paths. (useful for piping, e.g. into `wc -l`)
         #[arg(short, long)]
         found_paths_only: bool,
+
+        /// Show executables that shadow others of the same name, in real $PATH order.
+        #[arg(long)]
+        shadows: bool,
+
+        /// Check $PATH entries for problems and suggest a cleaned-up $PATH.
+        #[arg(long)]
+        audit: bool,
+
+        /// Walk $PATH entries fully, listing every file and directory (not just executables directly inside).
+        #[arg(short = 'R', long)]
+        recursive: bool,
+
+        /// Extra per-file columns, comma separated.
+        #[arg(short, long, value_delimiter = ',')]
+        columns: Vec<Column>,
+
+        /// Group found paths under the installer that put them there.
+        #[arg(short, long)]
+        group_by_source: bool,
+
+        /// Emit records in a machine-readable format instead of text.
+        #[arg(long, conflicts_with_all = ["shadows", "audit", "columns", "versions"])]
+        format: Option<OutputFormat>,
+
+        /// Run each winning executable with `--version` and tabulate what they report.
+        #[arg(long)]
+        versions: bool,
+
+        /// Argument passed when probing versions.
+        #[arg(long, default_value = "--version", allow_hyphen_values = true, requires = "versions")]
+        version_arg: String,
+
+        /// Seconds before a version probe is killed.
+        #[arg(long, default_value_t = 3.0, requires = "versions")]
+        timeout: f64,
+
+        /// Probes run at once (default: available parallelism).
+        #[arg(short, long, requires = "versions")]
+        jobs: Option<NonZeroUsize>,
+
+        /// Only probe these names (exact, glob, or `/regex/`).
+        #[arg(long, value_delimiter = ',', requires = "versions")]
+        only: Vec<String>,
+
+        /// Only probe names from the `NAME` column of this csv (e.g. `ad_deps.csv`).
+        #[arg(long, requires = "versions")]
+        deps_csv: Option<PathBuf>,
+
+        /// Probe every winning executable on $PATH (runs arbitrary binaries).
+        #[arg(long, requires = "versions", conflicts_with_all = ["only", "deps_csv"])]
+        all: bool,
+}
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+        /// One JSON array
+        Json,
+        /// One JSON object per line
+        Ndjson,
+        /// Comma separated, with header
+        Csv,
+        /// Tab separated, with header
+        Tsv,
 }
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Column {
+        /// Size in bytes (of the target, for symlinks)
+        Size,
+        /// Last modification time, local
+        Mtime,
+        /// Permission bits, `ls` style
+        Perms,
+        /// Symlink target, as written in the link
+        Target,
+        /// Whether a symlink's target is missing
+        Broken,
+        /// Installer that put it there (cargo, rustup, homebrew, nix, ...)
+        Source,
+}
+#[derive(Subcommand, Debug)]
+enum Command {
+        /// Find executables by name in $PATH precedence order (like `which -a`).
+        Which {
+                /// Exact name, glob (`py*`), or regex between slashes (`'/^py(thon)?3?$/'`)
+                #[arg(required = true)]
+                patterns: Vec<String>,
+        },
+        /// Record $PATH and the executables it resolves, as JSON.
+        Snapshot {
+                /// File to write (default: stdout)
+                #[arg(short, long)]
+                out: Option<PathBuf>,
+        },
+        /// Compare two snapshots, or one snapshot against the current environment.
+        Diff {
+                /// Earlier snapshot
+                before: PathBuf,
+                /// Later snapshot (default: the current environment)
+                after:  Option<PathBuf>,
+        },
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
         let args = Args::parse();
-        if 1 < [args.raw_paths, args.show_errors, args.found_paths_only].iter().filter(|&b| *b).count() {
-                Err("`raw_paths`, `show_errors`, and `found_paths_only` are mutually exclusive flags")?
+        match args.command {
+                | Some(Command::Which { patterns }) => {
+                        let shell_paths_os = env::var_os("PATH").expect(r#""PATH" not found."#);
+                        return which(&patterns, &env::split_paths(&shell_paths_os).collect::<Vec<_>>());
+                }
+                | Some(Command::Snapshot { out }) => {
+                        let json = serde_json::to_string_pretty(&Snapshot::current())?;
+                        match out {
+                                | Some(out) => fs::write(out, json + "\n")?,
+                                | None => println!("{json}"),
+                        }
+                        return Ok(())
+                }
+                | Some(Command::Diff { before, after }) => {
+                        let before = Snapshot::read(&before)?;
+                        let after = match after {
+                                | Some(after) => Snapshot::read(&after)?,
+                                | None => Snapshot::current(),
+                        };
+                        print!("{}", SnapshotDiff::between(&before, &after));
+                        return Ok(())
+                }
+                | None => {}
+        }
+        if 1 < [args.raw_paths, args.show_errors, args.found_paths_only, args.shadows, args.audit, args.versions].iter().filter(|&b| *b).count() {
+                Err("`raw_paths`, `show_errors`, `found_paths_only`, `shadows`, `audit`, and `versions` are mutually exclusive flags")?
         }
 
         let shell_paths_os = env::var_os("PATH").expect(r#""PATH" not found."#);
-        let mut path_vals: Vec<_> = env::split_paths(&shell_paths_os).collect();
-        path_vals.sort_unstable_by_key(|k| k.as_os_str().len());
+        // real $PATH order: index is precedence
+        let path_order: Vec<_> = env::split_paths(&shell_paths_os).collect();
+        if args.shadows {
+                let mut forbidden_map = HashMap::new();
+                let executables = scan_path(&path_order, false, false, &mut forbidden_map);
+                let shadows = Shadows::from_executables(executables, &SourceClassifier::from_env());
+                println!("{} ({} names in more than one $PATH entry):", "Shadowed executables".blue(), shadows.by_name.len().red());
+                println!("{}", shadows);
+                if !forbidden_map.is_empty() {
+                        println!("{} $PATH entries could not be read.", forbidden_map.values().map(Vec::len).sum::<usize>().red());
+                }
+                return Ok(())
+        }
+        if args.versions {
+                return versions(&args, &path_order);
+        }
+        if args.audit {
+                let audit = audit_path(&path_order);
+                println!("{} {} paths:", "Audit of".blue(), "$PATH".green());
+                for (i, (path, issues)) in path_order.iter().zip(&audit).enumerate() {
+                        if issues.is_empty() {
+                                println!("{:>3}  {} {}", i.blue(), "ok".green(), path.display().cyan());
+                                continue;
+                        }
+                        println!("{:>3}  {} {}", i.blue(), "!!".red(), path.display().cyan());
+                        for issue in issues {
+                                println!("        {}", issue);
+                        }
+                }
+                let flagged = audit.iter().filter(|issues| !issues.is_empty()).count();
+                println!("{} of {} entries flagged.", flagged.red(), path_order.len());
+                println!("{}", "Suggested $PATH:".blue());
+                println!("{}", suggested_path(&path_order, &audit)?.to_string_lossy());
+                return Ok(())
+        }
         if args.raw_paths {
+                if let Some(format) = args.format {
+                        let records: Vec<_> = path_order.iter().enumerate().map(|(i, p)| Record::raw_path(i, p)).collect();
+                        return write_records(io::stdout().lock(), format, &records);
+                }
+                let mut path_vals = path_order.clone();
+                path_vals.sort_unstable_by_key(|k| k.as_os_str().len());
                 println!("Raw {} paths:", "$PATH".green());
                 for (i, p) in path_vals.into_iter().enumerate() {
                         let p = match p.to_str() {
@@ -68,64 +316,1234 @@ fn main() -> Result<(), Box<dyn Error>> {
                 return Ok(())
         }
 
-        let mut found_paths = Vec::new();
         let mut forbidden_map = HashMap::new();
-        for uc_entry in path_vals.into_iter().flat_map(|p| WalkDir::new(p).into_iter()) {
-                match uc_entry {
-                        Ok(entry) => {
-                                let file = entry.file_name().to_string_lossy().into_owned();
-                                let path = entry.path().to_path_buf();
-                                found_paths.push(FoundPath{ file, path });
-                        },
-                        Err(err) => {
-                                let depth = err.depth();
-                                let path = err.path().unwrap_or(Path::new(""));
-                                let io_err = err.io_error().expect("walkdir error not wrapped io-error");
-                                forbidden_map.entry(io_err.kind()).or_insert_with(Vec::new).push((depth, path.to_path_buf()));
-                        },
+        let mut found_paths = scan_path(&path_order, args.recursive, args.columns.contains(&Column::Broken), &mut forbidden_map);
+        found_paths.sort_unstable();
+        // classified once each, and only if grouping, the column or the records use it
+        let classifier = (args.group_by_source || args.columns.contains(&Column::Source) || args.format.is_some()).then(SourceClassifier::from_env);
+        let mut found_paths: Vec<_> = found_paths
+                .into_iter()
+                .map(|found| {
+                        let source = classifier.as_ref().map(|classifier| classifier.classify(&found.path));
+                        (found, source)
+                })
+                .collect();
+        if args.group_by_source {
+                // stable: names stay sorted within each source
+                found_paths.sort_by_key(|&(_, source)| source);
+        }
+        if let Some(format) = args.format {
+                let mut records: Vec<_> = found_paths.iter().map(|(found, source)| Record::found(found, *source)).collect();
+                if args.show_errors {
+                        records.extend(forbidden_map.iter().flat_map(|(kind, errs)| errs.iter().map(move |err| Record::error(*kind, err))));
                 }
+                return write_records(io::stdout().lock(), format, &records);
         }
-        found_paths.sort_unstable();
-        let found_paths = FoundPaths {found_paths};
+        let found_paths = FoundPaths { found_paths, columns: args.columns, group_by_source: args.group_by_source };
         if !args.found_paths_only { println!("{}:", "Found paths".blue()); }
         println!("{}", found_paths); // Just doing formatting here would probably have been slightly better organizationally. (vs newtype)
         if args.show_errors {
                 println!("--------------- errors ---------------");
                 for key in forbidden_map.keys() {
                         println!("{:?}", key.red());
-                        for (depth, path) in forbidden_map.get(key).unwrap() {
+                        for (_, depth, path) in forbidden_map.get(key).unwrap() {
                                 println!("      at depth {:<-2}: {:->20}", depth.blue(), path.display().purple());
                         }
                 }
         } else if !forbidden_map.is_empty()  && !args.found_paths_only {
                println!("Some paths could not be fully processed.");
-               println!("{} errors were recorded during directory walk.", forbidden_map.len().red());
+               println!("{}", walk_error_summary(&forbidden_map));
                println!("Use the `{}` flag for greater visibility.", "--show-errors".cyan());
         }
         Ok(())
 }
 
+/// Walk errors by kind: ($PATH index, depth, path) of each.
+type ForbiddenMap = HashMap<io::ErrorKind, Vec<(usize, usize, PathBuf)>>;
+
+/// How many walk errors, of how many kinds.
+fn walk_error_summary(forbidden_map: &ForbiddenMap) -> String {
+        let error_count: usize = forbidden_map.values().map(Vec::len).sum();
+        format!("{} errors (of {} kinds) were recorded during directory walk.", error_count.red(), forbidden_map.len().red())
+}
+
+/// Files in each `$PATH` entry, in precedence order (sorted by name within an entry).
+///
+/// Default: executables directly inside each entry, following symlinks (a symlink to an executable counts),
+/// plus broken symlinks if `include_broken`.
+/// `recursive`: everything at any depth, the entry directory itself included.
+fn scan_path(path_order: &[PathBuf], recursive: bool, include_broken: bool, forbidden_map: &mut ForbiddenMap) -> Vec<FoundPath> {
+        let mut found_paths = Vec::new();
+        for (path_index, dir) in path_order.iter().enumerate() {
+                // an empty entry means "." to the shell; `WalkDir` won't take ""
+                let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir.as_path() };
+                let walker = match recursive {
+                        | true => WalkDir::new(dir),
+                        | false => WalkDir::new(dir).min_depth(1).max_depth(1),
+                };
+                for uc_entry in walker.sort_by_file_name() {
+                        match uc_entry {
+                                | Ok(entry) => {
+                                        let keep = recursive
+                                                || match fs::metadata(entry.path()) {
+                                                        | Ok(meta) => is_executable(&meta),
+                                                        | Err(_) => include_broken && entry.path_is_symlink(),
+                                                };
+                                        if keep {
+                                                let file = entry.file_name().to_string_lossy().into_owned();
+                                                let path = entry.path().to_path_buf();
+                                                found_paths.push(FoundPath { file, path, path_index, depth: entry.depth() });
+                                        }
+                                }
+                                | Err(err) => {
+                                        let depth = err.depth();
+                                        let path = err.path().unwrap_or(Path::new("")).to_path_buf();
+                                        let kind = err.io_error().map_or(io::ErrorKind::Other, io::Error::kind);
+                                        forbidden_map.entry(kind).or_default().push((path_index, depth, path));
+                                }
+                        }
+                }
+        }
+        found_paths
+}
+
+/// Something worth knowing about a `$PATH` entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum AuditIssue {
+        Empty,
+        Relative,
+        Missing,
+        NotDirectory,
+        /// Earlier kept index with the identical string.
+        Duplicate(usize),
+        /// Earlier kept index naming the same directory once canonicalized.
+        CanonicalDuplicate(usize, PathBuf),
+        GroupWritable,
+        WorldWritable,
+        Symlink(PathBuf),
+}
+impl AuditIssue {
+        /// Whether the suggested `$PATH` leaves this entry out.
+        fn drops_entry(&self) -> bool {
+                match self {
+                        | Self::Empty | Self::Relative | Self::Missing | Self::NotDirectory | Self::Duplicate(_) | Self::CanonicalDuplicate(..) => true,
+                        | Self::GroupWritable | Self::WorldWritable | Self::Symlink(_) => false,
+                }
+        }
+}
+impl Display for AuditIssue {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self {
+                        | Self::Empty => write!(f, "{} (searches the current directory)", "empty entry".red()),
+                        | Self::Relative => write!(f, "{} (resolved against the current directory)", "relative entry".red()),
+                        | Self::Missing => write!(f, "{}", "does not exist".red()),
+                        | Self::NotDirectory => write!(f, "{}", "not a directory".red()),
+                        | Self::Duplicate(i) => write!(f, "{} of {}", "duplicate".yellow(), i.blue()),
+                        | Self::CanonicalDuplicate(i, canonical) => {
+                                write!(f, "{} of {} (both are {})", "duplicate".yellow(), i.blue(), canonical.display().purple())
+                        }
+                        | Self::GroupWritable => write!(f, "{}", "group-writable".red()),
+                        | Self::WorldWritable => write!(f, "{}", "world-writable".red()),
+                        | Self::Symlink(target) => write!(f, "{} -> {}", "symlink".yellow(), target.display().purple()),
+                }
+        }
+}
+
+/// Issues for each `$PATH` entry, index-aligned with `path_order`.
+fn audit_path(path_order: &[PathBuf]) -> Vec<Vec<AuditIssue>> {
+        // only entries the suggested `$PATH` keeps count as a first occurrence: a dropped `bin` mustn't take an absolute `/…/bin` with it
+        let mut first_seen: HashMap<&Path, usize> = HashMap::new();
+        let mut first_canonical: HashMap<PathBuf, usize> = HashMap::new();
+        let mut audit = Vec::with_capacity(path_order.len());
+        for (i, path) in path_order.iter().enumerate() {
+                let mut issues = Vec::new();
+                if path.as_os_str().is_empty() {
+                        issues.push(AuditIssue::Empty);
+                } else if path.is_relative() {
+                        issues.push(AuditIssue::Relative);
+                }
+                if let Some(&earlier) = first_seen.get(path.as_path()) {
+                        issues.push(AuditIssue::Duplicate(earlier));
+                }
+                if let Ok(target) = fs::read_link(path) {
+                        issues.push(AuditIssue::Symlink(target));
+                }
+                // an empty entry means ".", which `metadata` won't accept as ""
+                let effective = if path.as_os_str().is_empty() { Path::new(".") } else { path.as_path() };
+                match fs::metadata(effective) {
+                        | Err(_) => issues.push(AuditIssue::Missing),
+                        | Ok(meta) if !meta.is_dir() => issues.push(AuditIssue::NotDirectory),
+                        | Ok(meta) => {
+                                let mode = meta.permissions().mode();
+                                if mode & 0o002 != 0 {
+                                        issues.push(AuditIssue::WorldWritable);
+                                } else if mode & 0o020 != 0 {
+                                        issues.push(AuditIssue::GroupWritable);
+                                }
+                        }
+                }
+                let canonical = fs::canonicalize(effective).ok();
+                if let Some(canonical) = &canonical {
+                        match first_canonical.get(canonical) {
+                                // exact duplicates were already reported (their first occurrence was checked here)
+                                | Some(&earlier) if !issues.iter().any(|issue| matches!(issue, AuditIssue::Duplicate(_))) => {
+                                        issues.push(AuditIssue::CanonicalDuplicate(earlier, canonical.clone()));
+                                }
+                                | _ => {}
+                        }
+                }
+                if !issues.iter().any(AuditIssue::drops_entry) {
+                        first_seen.insert(path, i);
+                        if let Some(canonical) = canonical {
+                                first_canonical.insert(canonical, i);
+                        }
+                }
+                audit.push(issues);
+        }
+        audit
+}
+
+/// `$PATH` without the entries whose issues drop them; order kept.
+fn suggested_path(path_order: &[PathBuf], audit: &[Vec<AuditIssue>]) -> Result<std::ffi::OsString, env::JoinPathsError> {
+        let kept = path_order.iter().zip(audit).filter(|(_, issues)| !issues.iter().any(AuditIssue::drops_entry)).map(|(path, _)| path);
+        env::join_paths(kept)
+}
+
+/// `which` subcommand: print every match per pattern; error if any pattern had none.
+fn which(patterns: &[String], path_order: &[PathBuf]) -> Result<(), Box<dyn Error>> {
+        let matchers = patterns.iter().map(|p| NameMatcher::new(p)).collect::<Result<Vec<_>, _>>()?;
+        let executables = scan_path(path_order, false, false, &mut HashMap::new());
+
+        let mut unmatched = Vec::new();
+        for (pattern, matcher) in patterns.iter().zip(&matchers) {
+                let matches: Vec<_> = executables.iter().filter(|found| matcher.matches(&found.file)).collect();
+                if matches.is_empty() {
+                        println!("{}: {}", pattern.red(), "no executable found in $PATH".red());
+                        unmatched.push(pattern.as_str());
+                        continue;
+                }
+                println!("{}:", pattern.green());
+                for found in matches {
+                        let chain = symlink_chain(&found.path);
+                        let mode = fs::metadata(&found.path).map(|meta| meta.permissions().mode()).unwrap_or(0);
+                        print!("  {:>3} {} {}", found.path_index.blue(), mode_string(mode).yellow(), found.path.display().cyan());
+                        for link in &chain {
+                                print!(" {} {}", "->".black(), link.display().purple());
+                        }
+                        println!();
+                }
+        }
+        if !unmatched.is_empty() {
+                Err(format!("Error: nothing in $PATH matches: {}", unmatched.join(", ")))?
+        }
+        Ok(())
+}
+
+/// How a `which` pattern compares against file names.
+enum NameMatcher {
+        Exact(String),
+        Glob(glob::Pattern),
+        Regex(Regex),
+}
+impl NameMatcher {
+        fn new(pattern: &str) -> Result<Self, Box<dyn Error>> {
+                if let Some(re) = pattern.strip_prefix('/').and_then(|p| p.strip_suffix('/')) {
+                        Ok(Self::Regex(Regex::new(re)?))
+                } else if pattern.contains(['*', '?', '[']) {
+                        Ok(Self::Glob(glob::Pattern::new(pattern)?))
+                } else {
+                        Ok(Self::Exact(pattern.to_string()))
+                }
+        }
+        fn matches(&self, name: &str) -> bool {
+                match self {
+                        | Self::Exact(exact) => name == exact,
+                        | Self::Glob(glob) => glob.matches(name),
+                        | Self::Regex(re) => re.is_match(name),
+                }
+        }
+}
+
+/// Each hop of a symlink, until a non-link (or a loop-ish 40 hops).  Empty if `path` isn't a symlink.
+fn symlink_chain(path: &Path) -> Vec<PathBuf> {
+        let mut chain = Vec::new();
+        let mut current = path.to_path_buf();
+        while chain.len() < 40 {
+                let Ok(target) = fs::read_link(&current) else { break };
+                // relative targets are relative to the link's own directory
+                current = current.parent().unwrap_or(Path::new("")).join(target);
+                chain.push(current.clone());
+        }
+        chain
+}
+
+/// `ls`-style permission bits, e.g. `rwxr-xr-x`.
+fn mode_string(mode: u32) -> String {
+        (0..9)
+                .map(|i| {
+                        let bit = 0o400 >> i;
+                        if mode & bit == 0 { '-' } else { ['r', 'w', 'x'][i % 3] }
+                })
+                .collect()
+}
+
+/// Regular file with any execute bit set.
+fn is_executable(meta: &fs::Metadata) -> bool {
+        meta.is_file() && meta.permissions().mode() & 0o111 != 0
+}
+
+/// Executable names found in more than one `$PATH` entry.
+/// Each list is in precedence order: the first one wins, the rest are shadowed.
+#[derive(Debug, Clone)]
+struct Shadows {
+        by_name: BTreeMap<String, Vec<(usize, PathBuf, Source)>>,
+}
+impl Shadows {
+        /// `executables` must already be in `$PATH` precedence order.
+        fn from_executables(executables: Vec<FoundPath>, classifier: &SourceClassifier) -> Self {
+                let mut by_name: BTreeMap<_, Vec<_>> = BTreeMap::new();
+                for FoundPath { file, path, path_index, .. } in executables {
+                        let source = classifier.classify(&path);
+                        by_name.entry(file).or_default().push((path_index, path, source));
+                }
+                by_name.retain(|_, entries| entries.len() > 1);
+                Self { by_name }
+        }
+}
+impl Display for Shadows {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                for (name, entries) in &self.by_name {
+                        writeln!(f, "{}", name.green())?;
+                        // e.g. `/bin -> /usr/bin`: the same file reached twice isn't really shadowed
+                        let winner = fs::canonicalize(&entries[0].1).ok();
+                        for (rank, (path_index, path, source)) in entries.iter().enumerate() {
+                                let dir = path.parent().unwrap_or(Path::new("")).display();
+                                let source = format!("{:<11}", source.to_string());
+                                if rank == 0 {
+                                        writeln!(f, "  {:>3} {} {} {}", path_index.blue(), "wins     ".green(), source.blue(), dir.cyan())?;
+                                } else if winner.is_some() && fs::canonicalize(path).ok() == winner {
+                                        writeln!(f, "  {:>3} {} {} {}", path_index.blue(), "same file".yellow(), source.blue(), dir.purple())?;
+                                } else {
+                                        writeln!(f, "  {:>3} {} {} {}", path_index.blue(), "shadowed ".red(), source.blue(), dir.purple())?;
+                                }
+                        }
+                }
+                Ok(())
+        }
+}
+
 /// NewType to enable Display and Comparison
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 struct FoundPath {
-        file: String,
-        path: PathBuf,
+        file:       String,
+        path:       PathBuf,
+        /// position of the containing entry in `$PATH` (precedence)
+        path_index: usize,
+        /// 0 is the `$PATH` entry itself
+        depth:      usize,
 }
 /// NewType to enable Display
 #[derive(Debug, Clone)]
 struct FoundPaths {
-        found_paths: Vec<FoundPath>,
+        /// each with its source, if classified (needed for grouping & the `source` column)
+        found_paths:     Vec<(FoundPath, Option<Source>)>,
+        columns:         Vec<Column>,
+        /// print a header whenever the source changes (`found_paths` must be sorted by source)
+        group_by_source: bool,
 }
 impl Display for FoundPaths {
         fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-                for path in self.found_paths.iter() {
-                        writeln!(f, "{}", path)?;
+                let mut current_source = None;
+                for (path, source) in self.found_paths.iter() {
+                        if self.group_by_source
+                                && let Some(source) = *source
+                                && current_source != Some(source)
+                        {
+                                writeln!(f, "{}:", source.blue())?;
+                                current_source = Some(source);
+                        }
+                        // fixed-width columns lead, so they line up; variable ones trail
+                        let (lstat, stat) = (fs::symlink_metadata(&path.path).ok(), fs::metadata(&path.path).ok());
+                        let is_link = lstat.as_ref().is_some_and(fs::Metadata::is_symlink);
+                        let meta = stat.as_ref().or(lstat.as_ref());
+                        for column in &self.columns {
+                                match column {
+                                        | Column::Perms => write!(f, "{} ", meta.map_or("?????????".into(), |m| mode_string(m.permissions().mode())).yellow())?,
+                                        | Column::Size => write!(f, "{:>10} ", meta.map_or("?".into(), |m| m.len().to_string()))?,
+                                        | Column::Mtime => write!(f, "{} ", meta.and_then(|m| m.modified().ok()).map_or("????-??-?? ??:??".into(), format_mtime).blue())?,
+                                        | Column::Source => write!(f, "{:<11} ", source.map_or("?".into(), |s| s.to_string()).blue())?,
+                                        | Column::Target | Column::Broken => {}
+                                }
+                        }
+                        write!(f, "{}", path)?;
+                        if self.columns.contains(&Column::Target) && is_link {
+                                let target = fs::read_link(&path.path).map_or("?".into(), |t| t.display().to_string());
+                                write!(f, " {} {}", "->".black(), target.purple())?;
+                        }
+                        if self.columns.contains(&Column::Broken) && is_link && stat.is_none() {
+                                write!(f, " {}", "(broken)".red())?;
+                        }
+                        writeln!(f)?;
+                }
+                Ok(())
+        }
+}
+
+/// `$PATH` and the executables it resolves to, at some moment in some environment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Snapshot {
+        taken_at:    String,
+        path:        Vec<PathBuf>,
+        /// default scan: precedence order, by name within a `$PATH` entry
+        executables: Vec<FoundPath>,
+}
+impl Snapshot {
+        fn current() -> Self {
+                let shell_paths_os = env::var_os("PATH").expect(r#""PATH" not found."#);
+                let path: Vec<_> = env::split_paths(&shell_paths_os).collect();
+                let executables = scan_path(&path, false, false, &mut HashMap::new());
+                Self { taken_at: jiff::Timestamp::now().to_string(), path, executables }
+        }
+        fn read(file: &Path) -> Result<Self, Box<dyn Error>> {
+                let json = fs::read_to_string(file).map_err(|e| format!("Error: reading snapshot {}: {e}", file.display()))?;
+                Ok(serde_json::from_str(&json).map_err(|e| format!("Error: parsing snapshot {}: {e}", file.display()))?)
+        }
+        fn winners(&self) -> BTreeMap<&str, &Path> {
+                winners(&self.executables)
+        }
+}
+
+/// What the shell would run for each name: the first in precedence order.
+fn winners(executables: &[FoundPath]) -> BTreeMap<&str, &Path> {
+        let mut winners = BTreeMap::new();
+        for found in executables {
+                winners.entry(found.file.as_str()).or_insert(found.path.as_path());
+        }
+        winners
+}
+
+/// `--versions` mode: probe winners in parallel, then print a table sorted by name.
+fn versions(args: &Args, path_order: &[PathBuf]) -> Result<(), Box<dyn Error>> {
+        let mut matchers = args.only.iter().map(|p| NameMatcher::new(p)).collect::<Result<Vec<_>, _>>()?;
+        if let Some(csv_path) = &args.deps_csv {
+                let mut reader = csv::Reader::from_path(csv_path)?;
+                let name_col = reader.headers()?.iter().position(|h| h == "NAME").ok_or("Error: csv has no `NAME` column")?;
+                for row in reader.records() {
+                        match row?.get(name_col) {
+                                // `___` marks an empty cell
+                                | Some(name) if !name.is_empty() && name != "___" => matchers.push(NameMatcher::Exact(name.to_string())),
+                                | _ => {}
+                        }
+                }
+        }
+        // no matchers would mean everything: only on request
+        if matchers.is_empty() && !args.all {
+                Err("Error: `--versions` runs what it probes; pick names with `--only` or `--deps-csv`, or pass `--all`")?
+        }
+        let timeout = Duration::try_from_secs_f64(args.timeout).map_err(|e| format!("Error: bad `--timeout`: {e}"))?;
+        let jobs = args.jobs.or_else(|| thread::available_parallelism().ok()).map_or(1, NonZeroUsize::get);
+
+        let executables = scan_path(path_order, false, false, &mut HashMap::new());
+        let targets: Vec<_> = winners(&executables)
+                .into_iter()
+                .filter(|(name, _)| args.all || matchers.iter().any(|m| m.matches(name)))
+                .collect();
+        let unfound: Vec<_> = matchers
+                .iter()
+                .filter_map(|m| match m {
+                        | NameMatcher::Exact(name) if !targets.iter().any(|(n, _)| n == name) => Some(name.as_str()),
+                        | _ => None,
+                })
+                .collect();
+
+        // workers pull the next index until the list is exhausted
+        let next = AtomicUsize::new(0);
+        let probes = Mutex::new(Vec::with_capacity(targets.len()));
+        thread::scope(|scope| {
+                for _ in 0..jobs.min(targets.len()) {
+                        scope.spawn(|| {
+                                while let Some(&(name, path)) = targets.get(next.fetch_add(1, Ordering::Relaxed)) {
+                                        let probe = probe_version(path, &args.version_arg, timeout);
+                                        probes.lock().expect("no panics while holding the lock").push((name, path, probe));
+                                }
+                        });
+                }
+        });
+        let mut probes = probes.into_inner().expect("workers finished");
+        probes.sort_unstable_by_key(|&(name, ..)| name);
+
+        let name_width = probes.iter().map(|(name, ..)| name.len()).max().unwrap_or(4).max(4);
+        println!("{:<name_width$}  {:<9}  {:<40}  {}", "name".blue(), "status".blue(), "version".blue(), "path".blue());
+        for (name, path, probe) in &probes {
+                let version = probe.version.as_deref().unwrap_or("-");
+                let status = match probe.status {
+                        | ProbeStatus::Exited(0) => "ok".green().to_string(),
+                        | ProbeStatus::Exited(code) => format!("exit {code}").yellow().to_string(),
+                        | ProbeStatus::Signaled => "signaled".red().to_string(),
+                        | ProbeStatus::TimedOut => "timeout".red().to_string(),
+                        | ProbeStatus::SpawnFailed => "no spawn".red().to_string(),
+                };
+                // pad by hand: ANSI codes would count towards `{:<9}`
+                let pad = 9usize.saturating_sub(anstream::adapter::strip_str(&status).to_string().len());
+                println!("{:<name_width$}  {status}{:pad$}  {:<40}  {}", name.green(), "", version, path.display().cyan());
+        }
+        let timed_out = probes.iter().filter(|(.., p)| p.status == ProbeStatus::TimedOut).count();
+        println!("{} probed, {} timed out (killed after {:?}).", probes.len(), timed_out.red(), timeout);
+        if !unfound.is_empty() {
+                println!("{} {}", "Not on $PATH:".red(), unfound.join(", "));
+        }
+        Ok(())
+}
+
+/// How a version probe ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProbeStatus {
+        Exited(i32),
+        Signaled,
+        TimedOut,
+        SpawnFailed,
+}
+#[derive(Debug, Clone)]
+struct Probe {
+        status:  ProbeStatus,
+        version: Option<String>,
+}
+
+static VERSION_LIKE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\d+\.\d+").expect("valid regex"));
+
+/// Run `path arg` with stdin closed and output captured; its whole process group is killed once it exits or `timeout` passes.
+fn probe_version(path: &Path, arg: &str, timeout: Duration) -> Probe {
+        let spawned = Process::new(path)
+                .arg(arg)
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                // own group, so a timeout also takes out anything it forked (which would hold the pipes open)
+                .process_group(0)
+                .spawn();
+        let Ok(mut child) = spawned else {
+                return Probe { status: ProbeStatus::SpawnFailed, version: None };
+        };
+        let (mut stdout, mut stderr) = (child.stdout.take().expect("piped"), child.stderr.take().expect("piped"));
+        // non-blocking, so nothing here outlives the deadline: whatever inherited the pipes may keep them open
+        set_nonblocking(&stdout);
+        set_nonblocking(&stderr);
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        let deadline = Instant::now() + timeout;
+        let exited = loop {
+                // drain both pipes while waiting, or a chatty child blocks on a full pipe
+                drain(&mut stdout, &mut out);
+                drain(&mut stderr, &mut err);
+                if has_exited(&child) {
+                        break true;
+                }
+                if Instant::now() >= deadline {
+                        break false;
+                }
+                thread::sleep(Duration::from_millis(10));
+        };
+        // the whole group goes, on exit too: anything it left running would hold the pipes open (or just linger)
+        // SAFETY: plain syscall; the negative pid targets the group `process_group(0)` created,
+        //         whose id can't be reused while its leader is still unreaped
+        unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) };
+        let waited = child.wait();
+        drain(&mut stdout, &mut out);
+        drain(&mut stderr, &mut err);
+        let status = match (exited, waited) {
+                | (true, Ok(status)) => status.code().map_or(ProbeStatus::Signaled, ProbeStatus::Exited),
+                | _ => ProbeStatus::TimedOut,
+        };
+        let version = [&out, &err]
+                .into_iter()
+                .flat_map(|bytes| String::from_utf8_lossy(bytes).lines().map(str::trim).map(String::from).collect::<Vec<_>>())
+                .find(|line| VERSION_LIKE.is_match(line));
+        Probe { status, version }
+}
+
+/// Whether `child` has exited, without reaping it (`WNOWAIT`): its pid stays taken until `wait`.
+fn has_exited(child: &std::process::Child) -> bool {
+        // SAFETY: `siginfo_t` is plain data; `waitid` only writes into it
+        unsafe {
+                let mut info: libc::siginfo_t = std::mem::zeroed();
+                let flags = libc::WEXITED | libc::WNOHANG | libc::WNOWAIT;
+                // an error (no such child) counts as exited; `wait` reports it
+                libc::waitid(libc::P_PID, child.id(), &mut info, flags) != 0 || info.si_pid() != 0
+        }
+}
+
+fn set_nonblocking(pipe: &impl AsRawFd) {
+        // SAFETY: plain syscalls on a descriptor we own
+        unsafe {
+                let flags = libc::fcntl(pipe.as_raw_fd(), libc::F_GETFL);
+                libc::fcntl(pipe.as_raw_fd(), libc::F_SETFL, flags | libc::O_NONBLOCK);
+        }
+}
+
+/// Read whatever a non-blocking pipe has right now.
+fn drain(pipe: &mut impl Read, into: &mut Vec<u8>) {
+        let mut buf = [0; 8192];
+        while let Ok(n @ 1..) = pipe.read(&mut buf) {
+                into.extend_from_slice(&buf[..n]);
+        }
+}
+
+/// Changes from one [`Snapshot`] to another.
+#[derive(Debug, Clone)]
+struct SnapshotDiff<'a> {
+        dirs_added:     Vec<&'a Path>,
+        dirs_removed:   Vec<&'a Path>,
+        exes_added:     Vec<(&'a str, &'a Path)>,
+        exes_removed:   Vec<(&'a str, &'a Path)>,
+        /// name, before, after
+        winner_changed: Vec<(&'a str, &'a Path, &'a Path)>,
+        before_at:      &'a str,
+        after_at:       &'a str,
+}
+impl<'a> SnapshotDiff<'a> {
+        fn between(before: &'a Snapshot, after: &'a Snapshot) -> Self {
+                let dirs_added = after.path.iter().filter(|p| !before.path.contains(p)).map(PathBuf::as_path).collect();
+                let dirs_removed = before.path.iter().filter(|p| !after.path.contains(p)).map(PathBuf::as_path).collect();
+                let (old, new) = (before.winners(), after.winners());
+                let exes_added = new.iter().filter(|(name, _)| !old.contains_key(*name)).map(|(&n, &p)| (n, p)).collect();
+                let exes_removed = old.iter().filter(|(name, _)| !new.contains_key(*name)).map(|(&n, &p)| (n, p)).collect();
+                let winner_changed = old
+                        .iter()
+                        .filter_map(|(&name, &was)| new.get(name).filter(|&&now| now != was).map(|&now| (name, was, now)))
+                        .collect();
+                Self {
+                        dirs_added,
+                        dirs_removed,
+                        exes_added,
+                        exes_removed,
+                        winner_changed,
+                        before_at: &before.taken_at,
+                        after_at: &after.taken_at,
+                }
+        }
+}
+impl Display for SnapshotDiff<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                writeln!(f, "{} {} -> {}", "Diff".blue(), self.before_at, self.after_at)?;
+                writeln!(f, "{} ({}):", "$PATH directories".blue(), self.dirs_added.len() + self.dirs_removed.len())?;
+                for dir in &self.dirs_removed {
+                        writeln!(f, "  {} {}", "-".red(), dir.display().red())?;
+                }
+                for dir in &self.dirs_added {
+                        writeln!(f, "  {} {}", "+".green(), dir.display().green())?;
+                }
+                writeln!(f, "{} ({}):", "Winner changed".blue(), self.winner_changed.len())?;
+                for (name, was, now) in &self.winner_changed {
+                        writeln!(f, "  {:<20} {} {} {}", name.yellow(), was.display().red(), "->".black(), now.display().green())?;
+                }
+                writeln!(f, "{} ({}):", "Executables gone".blue(), self.exes_removed.len())?;
+                for (name, was) in &self.exes_removed {
+                        writeln!(f, "  {} {:<20} {}", "-".red(), name.red(), was.display())?;
+                }
+                writeln!(f, "{} ({}):", "Executables new".blue(), self.exes_added.len())?;
+                for (name, now) in &self.exes_added {
+                        writeln!(f, "  {} {:<20} {}", "+".green(), name.green(), now.display())?;
                 }
                 Ok(())
         }
 }
+
+/// Installer responsible for an executable.  Ordered roughly user-level first, for grouping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum Source {
+        Cargo,
+        Rustup,
+        UserLocal,
+        Pyenv,
+        PyenvShim,
+        RyeShim,
+        Conda,
+        Homebrew,
+        Nix,
+        UsrLocal,
+        System,
+        Other,
+}
+impl Display for Source {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let name = match self {
+                        | Self::Cargo => "cargo",
+                        | Self::Rustup => "rustup",
+                        | Self::UserLocal => "user-local",
+                        | Self::Pyenv => "pyenv",
+                        | Self::PyenvShim => "pyenv-shim",
+                        | Self::RyeShim => "rye-shim",
+                        | Self::Conda => "conda",
+                        | Self::Homebrew => "homebrew",
+                        | Self::Nix => "nix",
+                        | Self::UsrLocal => "usr-local",
+                        | Self::System => "system",
+                        | Self::Other => "other",
+                };
+                f.pad(name)
+        }
+}
+
+/// Prefix rules plus the `rustup` binary's fingerprint, resolved once from the environment.
+#[derive(Debug, Clone)]
+struct SourceClassifier {
+        /// longest prefix wins, so `~/.cargo/bin` beats nothing and `/usr/local/Cellar` beats `/usr/local`
+        prefixes: Vec<(PathBuf, Source)>,
+        /// (size, content hash) of `rustup`, which every proxy is a copy of
+        rustup:   Option<(u64, u64)>,
+}
+impl SourceClassifier {
+        fn from_env() -> Self {
+                let home = env::var_os("HOME").map(PathBuf::from);
+                let home_or = |var: &str, rel: &str| env::var_os(var).map(PathBuf::from).or_else(|| home.as_ref().map(|h| h.join(rel)));
+                let cargo_home = home_or("CARGO_HOME", ".cargo");
+                let rustup_home = home_or("RUSTUP_HOME", ".rustup");
+                let pyenv_root = home_or("PYENV_ROOT", ".pyenv");
+                let rye_home = home_or("RYE_HOME", ".rye");
+
+                let mut prefixes: Vec<(PathBuf, Source)> = [
+                        ("/usr/bin", Source::System),
+                        ("/bin", Source::System),
+                        ("/usr/sbin", Source::System),
+                        ("/sbin", Source::System),
+                        ("/usr/local", Source::UsrLocal),
+                        ("/usr/local/Cellar", Source::Homebrew),
+                        ("/usr/local/Homebrew", Source::Homebrew),
+                        ("/opt/homebrew", Source::Homebrew),
+                        ("/home/linuxbrew/.linuxbrew", Source::Homebrew),
+                        ("/nix", Source::Nix),
+                        ("/run/current-system/sw", Source::Nix),
+                        ("/etc/profiles/per-user", Source::Nix),
+                ]
+                .into_iter()
+                .map(|(p, s)| (PathBuf::from(p), s))
+                .collect();
+                let mut add = |path: Option<PathBuf>, source| prefixes.extend(path.map(|p| (p, source)));
+                add(cargo_home.map(|p| p.join("bin")), Source::Cargo);
+                add(rustup_home, Source::Rustup);
+                add(pyenv_root.clone(), Source::Pyenv);
+                add(pyenv_root.map(|p| p.join("shims")), Source::PyenvShim);
+                add(rye_home.map(|p| p.join("shims")), Source::RyeShim);
+                add(env::var_os("CONDA_PREFIX").map(PathBuf::from), Source::Conda);
+                for rel in [".local/bin", ".nix-profile", "miniconda3", "miniconda", "anaconda3", "miniforge3", ".linuxbrew"] {
+                        let source = match rel {
+                                | ".local/bin" => Source::UserLocal,
+                                | ".nix-profile" => Source::Nix,
+                                | ".linuxbrew" => Source::Homebrew,
+                                | _ => Source::Conda,
+                        };
+                        add(home.as_ref().map(|h| h.join(rel)), source);
+                }
+
+                // the `rustup` that would run, else the one rustup installs by default
+                let rustup_bin = env::var_os("PATH")
+                        .into_iter()
+                        .flat_map(|paths| env::split_paths(&paths).collect::<Vec<_>>())
+                        .map(|dir| dir.join("rustup"))
+                        .find(|p| p.is_file());
+                let rustup = rustup_bin.and_then(|p| fingerprint(&p));
+                Self { prefixes, rustup }
+        }
+
+        fn classify(&self, path: &Path) -> Source {
+                if let Some((size, hash)) = self.rustup
+                        && fs::metadata(path).is_ok_and(|m| m.len() == size)
+                        && fingerprint(path) == Some((size, hash))
+                {
+                        return Source::Rustup;
+                }
+                // a symlink's target says more about its installer (`/usr/local/bin/x -> ../Cellar/..`, `/nix/store/..`)
+                let resolved = fs::canonicalize(path).ok();
+                [resolved.as_deref(), Some(path)]
+                        .into_iter()
+                        .flatten()
+                        .find_map(|p| {
+                                self.prefixes
+                                        .iter()
+                                        .filter(|(prefix, _)| p.starts_with(prefix))
+                                        .max_by_key(|(prefix, _)| prefix.as_os_str().len())
+                                        .map(|&(_, source)| source)
+                                        // a system path resolved from elsewhere (`/bin -> /usr/bin`) says nothing new
+                                        .filter(|&source| source != Source::System || p == path)
+                        })
+                        .unwrap_or(Source::Other)
+        }
+}
+
+/// (size, hash of contents).  Only compared within one run, so std's hasher is fine.
+fn fingerprint(path: &Path) -> Option<(u64, u64)> {
+        let bytes = fs::read(path).ok()?;
+        let mut hasher = DefaultHasher::new();
+        hasher.write(&bytes);
+        Some((bytes.len() as u64, hasher.finish()))
+}
+
+/// One row of `--format` output.  Found files, raw `$PATH` entries, and walk errors share the shape.
+#[derive(Debug, Clone, Serialize)]
+struct Record {
+        kind:       &'static str,
+        file:       Option<String>,
+        path:       String,
+        path_index: usize,
+        depth:      Option<usize>,
+        error_kind: Option<String>,
+        source:     Option<Source>,
+}
+impl Record {
+        fn found(found: &FoundPath, source: Option<Source>) -> Self {
+                Self {
+                        kind:       "file",
+                        file:       Some(found.file.clone()),
+                        path:       found.path.to_string_lossy().into_owned(),
+                        path_index: found.path_index,
+                        depth:      Some(found.depth),
+                        error_kind: None,
+                        source,
+                }
+        }
+        fn raw_path(path_index: usize, path: &Path) -> Self {
+                Self {
+                        kind: "path",
+                        file: None,
+                        path: path.to_string_lossy().into_owned(),
+                        path_index,
+                        depth: None,
+                        error_kind: None,
+                        source: None,
+                }
+        }
+        fn error(kind: io::ErrorKind, (path_index, depth, path): &(usize, usize, PathBuf)) -> Self {
+                Self {
+                        kind:       "error",
+                        file:       None,
+                        path:       path.to_string_lossy().into_owned(),
+                        path_index: *path_index,
+                        depth:      Some(*depth),
+                        error_kind: Some(format!("{kind:?}")),
+                        source:     None,
+                }
+        }
+}
+
+fn write_records(mut out: impl Write, format: OutputFormat, records: &[Record]) -> Result<(), Box<dyn Error>> {
+        match format {
+                | OutputFormat::Json => {
+                        serde_json::to_writer_pretty(&mut out, records)?;
+                        writeln!(out)?;
+                }
+                | OutputFormat::Ndjson => {
+                        for record in records {
+                                serde_json::to_writer(&mut out, record)?;
+                                writeln!(out)?;
+                        }
+                }
+                | OutputFormat::Csv | OutputFormat::Tsv => {
+                        let delimiter = if format == OutputFormat::Csv { b',' } else { b'\t' };
+                        let mut writer = csv::WriterBuilder::new().delimiter(delimiter).from_writer(out);
+                        for record in records {
+                                writer.serialize(record)?;
+                        }
+                        writer.flush()?;
+                }
+        }
+        Ok(())
+}
+
+/// Local `YYYY-MM-DD HH:MM`.
+fn format_mtime(time: std::time::SystemTime) -> String {
+        jiff::Timestamp::try_from(time)
+                .map(|ts| ts.to_zoned(jiff::tz::TimeZone::system()).strftime("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_else(|_| "????-??-?? ??:??".into())
+}
 impl Display for FoundPath {
         fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
                 write!(f, "{:-<8}: {:->20}", self.file.green(), self.path.display())
         }
 }
+
+#[cfg(test)]
+mod tests {
+        use std::os::unix::fs::symlink;
+
+        use super::*;
+
+        #[test]
+        fn audit_issues_and_suggestion() {
+                let dir = tempfile::tempdir().unwrap();
+                let root = fs::canonicalize(dir.path()).unwrap();
+                let mkdir = |name: &str, mode: u32| {
+                        let path = root.join(name);
+                        fs::create_dir(&path).unwrap();
+                        fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+                        path
+                };
+                let real = mkdir("real", 0o755);
+                let open = mkdir("open", 0o777);
+                let shared = mkdir("shared", 0o775);
+                let link = root.join("link");
+                symlink(&real, &link).unwrap();
+                let file = root.join("file");
+                fs::write(&file, "").unwrap();
+                let path_order: Vec<PathBuf> = [
+                        real.clone(),
+                        link.clone(),
+                        real.clone(),
+                        PathBuf::new(),
+                        PathBuf::from("no/such/relative"),
+                        root.join("missing"),
+                        open.clone(),
+                        shared.clone(),
+                        file,
+                        link.clone(),
+                ]
+                .into();
+
+                use AuditIssue::*;
+                let audit = audit_path(&path_order);
+                assert_eq!(audit[0], []);
+                // same directory under another name: canonical duplicate, and flagged as a symlink
+                assert_eq!(audit[1], [Symlink(real.clone()), CanonicalDuplicate(0, real.clone())]);
+                // identical string: an exact duplicate only, not a canonical one as well
+                assert_eq!(audit[2], [Duplicate(0)]);
+                // "" is searched as `.`, so it exists; whatever else the working dir is, the entry's empty
+                assert_eq!(audit[3][0], Empty);
+                assert!(!audit[3].contains(&Missing));
+                assert_eq!(audit[4], [Relative, Missing]);
+                assert_eq!(audit[5], [Missing]);
+                assert_eq!(audit[6], [WorldWritable]);
+                assert_eq!(audit[7], [GroupWritable]);
+                assert_eq!(audit[8], [NotDirectory]);
+                // the first link was dropped, so a repeat of it is reported against the kept `real`
+                assert_eq!(audit[9], [Symlink(real.clone()), CanonicalDuplicate(0, real.clone())]);
+
+                // duplicates, empty/relative/missing entries and non-directories go; writable dirs and symlinks are only flagged
+                let expected = env::join_paths([&real, &open, &shared]).unwrap();
+                assert_eq!(suggested_path(&path_order, &audit).unwrap(), expected);
+
+                // a dropped relative entry for `real` doesn't make the absolute one a duplicate; the link's report points at the kept one
+                let cwd = env::current_dir().unwrap();
+                let relative: PathBuf = cwd.components().skip(1).map(|_| Path::new("..")).chain([real.strip_prefix("/").unwrap()]).collect();
+                let path_order = [relative, real.clone(), link.clone()];
+                let audit = audit_path(&path_order);
+                assert_eq!(audit, [vec![Relative], vec![], vec![Symlink(real.clone()), CanonicalDuplicate(1, real.clone())]]);
+                assert_eq!(suggested_path(&path_order, &audit).unwrap(), env::join_paths([&real]).unwrap());
+        }
+
+        #[test]
+        fn classify_sources() {
+                let dir = tempfile::tempdir().unwrap();
+                let root = fs::canonicalize(dir.path()).unwrap();
+                let file = |rel: &str, contents: &str| {
+                        let path = root.join(rel);
+                        fs::create_dir_all(path.parent().unwrap()).unwrap();
+                        fs::write(&path, contents).unwrap();
+                        path
+                };
+                let cargo_bin = root.join("home/.cargo/bin");
+                let rustup = file("home/.cargo/bin/rustup", "rustup multiplexer");
+                let python = file("usr/bin/python3", "#!python");
+                let cellar_foo = file("usr/local/Cellar/foo/1.0/bin/foo", "foo");
+                let plain = file("usr/local/bin/plain", "plain");
+                let own_tool = file("home/.cargo/bin/own-tool", "rustup lookalike"); // same size as `rustup`
+                let elsewhere = file("opt/thing/bin/thing", "thing");
+
+                let proxy = cargo_bin.join("cargo");
+                fs::hard_link(&rustup, &proxy).unwrap();
+                let copied_proxy = root.join("usr/local/bin/rustc");
+                fs::copy(&rustup, &copied_proxy).unwrap();
+                let brew_link = root.join("usr/local/bin/foo");
+                symlink("../Cellar/foo/1.0/bin/foo", &brew_link).unwrap();
+                let local_link = root.join("home/.local/bin/python");
+                fs::create_dir_all(local_link.parent().unwrap()).unwrap();
+                symlink(&python, &local_link).unwrap();
+                let bare_link = root.join("opt/thing/bin/py");
+                symlink(&python, &bare_link).unwrap();
+
+                let classifier = SourceClassifier {
+                        prefixes: [
+                                ("usr/bin", Source::System),
+                                ("usr/local", Source::UsrLocal),
+                                ("usr/local/Cellar", Source::Homebrew),
+                                ("home/.cargo/bin", Source::Cargo),
+                                ("home/.local/bin", Source::UserLocal),
+                        ]
+                        .into_iter()
+                        .map(|(rel, source)| (root.join(rel), source))
+                        .collect(),
+                        rustup:   fingerprint(&rustup),
+                };
+                let cases = [
+                        // proxies are `rustup` itself, wherever they are and however they got there
+                        (&rustup, Source::Rustup),
+                        (&proxy, Source::Rustup),
+                        (&copied_proxy, Source::Rustup),
+                        (&own_tool, Source::Cargo),
+                        // longest prefix wins, through the symlink if there is one
+                        (&cellar_foo, Source::Homebrew),
+                        (&brew_link, Source::Homebrew),
+                        (&plain, Source::UsrLocal),
+                        // resolving to a system path says nothing new: the link's own location decides
+                        (&python, Source::System),
+                        (&local_link, Source::UserLocal),
+                        (&bare_link, Source::Other),
+                        (&elsewhere, Source::Other),
+                ];
+                for (path, expected) in cases {
+                        assert_eq!(classifier.classify(path), expected, "{}", path.display());
+                }
+        }
+
+        #[test]
+        fn snapshot_diff() {
+                let found = |file: &str, dir: &str, path_index| FoundPath {
+                        file: file.to_string(),
+                        path: Path::new(dir).join(file),
+                        path_index,
+                        depth: 1,
+                };
+                let before = Snapshot {
+                        taken_at:    "then".to_string(),
+                        path:        vec!["/a".into(), "/b".into()],
+                        executables: vec![found("ls", "/a", 0), found("old", "/a", 0), found("vim", "/b", 1)],
+                };
+                let after = Snapshot {
+                        taken_at:    "now".to_string(),
+                        path:        vec!["/c".into(), "/a".into()],
+                        executables: vec![found("ls", "/c", 0), found("ls", "/a", 1), found("new", "/a", 1)],
+                };
+                let diff = SnapshotDiff::between(&before, &after);
+                let path = Path::new;
+                assert_eq!(diff.dirs_added, [path("/c")]);
+                assert_eq!(diff.dirs_removed, [path("/b")]);
+                assert_eq!(diff.exes_added, [("new", path("/a/new"))]);
+                assert_eq!(diff.exes_removed, [("old", path("/a/old")), ("vim", path("/b/vim"))]);
+                // `/a/ls` is still there, but no longer what runs
+                assert_eq!(diff.winner_changed, [("ls", path("/a/ls"), path("/c/ls"))]);
+                assert_eq!((diff.before_at, diff.after_at), ("then", "now"));
+
+                let same = SnapshotDiff::between(&after, &after);
+                assert!(same.dirs_added.is_empty() && same.exes_removed.is_empty() && same.winner_changed.is_empty());
+        }
+
+        #[test]
+        fn versions_needs_a_selection() {
+                let args = Args::try_parse_from(["sort-path", "--versions"]).unwrap();
+                assert!(versions(&args, &[]).is_err());
+                assert!(Args::try_parse_from(["sort-path", "--versions", "--all", "--only", "ls"]).is_err());
+                assert!(Args::try_parse_from(["sort-path", "--all"]).is_err());
+        }
+
+        #[test]
+        fn probe_timeouts_and_versions() {
+                let dir = tempfile::tempdir().unwrap();
+                // every script is written before any is spawned: a fork elsewhere mid-write would make exec fail with ETXTBSY
+                let script = |name: &str, body: &str| {
+                        let path = dir.path().join(name);
+                        fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+                        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+                        path
+                };
+                let quick = script("quick", "echo 'usage: quick [--version]'\necho 'quick version 3.14' >&2\nexit 2");
+                let hangs = script("hangs", "exec sleep 30");
+                let pid_file = dir.path().join("background.pid");
+                let forks = script("forks", &format!("sleep 30 &\necho $! > '{}'\necho 'forks 1.2'\nwait", pid_file.display()));
+                // alive, and not just a zombie left for init to reap
+                let running = |pid: &str| {
+                        fs::read_to_string(format!("/proc/{pid}/stat"))
+                                .is_ok_and(|stat| stat.rsplit_once(')').is_some_and(|(_, rest)| !rest.trim_start().starts_with('Z')))
+                };
+
+                // no version on stdout: the first version-looking line of stderr
+                let probe = probe_version(&quick, "--version", Duration::from_secs(5));
+                assert_eq!(probe.status, ProbeStatus::Exited(2));
+                assert_eq!(probe.version.as_deref(), Some("quick version 3.14"));
+
+                let started = Instant::now();
+                let probe = probe_version(&hangs, "--version", Duration::from_millis(200));
+                assert_eq!(probe.status, ProbeStatus::TimedOut);
+                assert!(started.elapsed() < Duration::from_secs(5), "{:?}", started.elapsed());
+
+                // the background child holds the pipes; it's killed with the group, and output up to the deadline is kept
+                let started = Instant::now();
+                let probe = probe_version(&forks, "--version", Duration::from_millis(300));
+                assert_eq!(probe.status, ProbeStatus::TimedOut);
+                assert!(started.elapsed() < Duration::from_secs(5), "{:?}", started.elapsed());
+                assert_eq!(probe.version.as_deref(), Some("forks 1.2"));
+                let background = fs::read_to_string(&pid_file).unwrap();
+                let gone_by = Instant::now() + Duration::from_secs(2);
+                while running(background.trim()) && Instant::now() < gone_by {
+                        thread::sleep(Duration::from_millis(10));
+                }
+                assert!(!running(background.trim()), "background child {} survived", background.trim());
+        }
+
+        #[test]
+        fn which_matching() {
+                let dir = tempfile::tempdir().unwrap();
+                let root = fs::canonicalize(dir.path()).unwrap();
+                let (first, second) = (root.join("first"), root.join("second"));
+                fs::create_dir(&first).unwrap();
+                fs::create_dir(&second).unwrap();
+                let file = |path: &Path, mode: u32| {
+                        fs::write(path, "").unwrap();
+                        fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+                };
+                file(&first.join("tool"), 0o755);
+                file(&second.join("tool"), 0o755);
+                file(&first.join("notes.txt"), 0o644);
+                // second/hop -> link -> ../first/tool: relative targets, resolved from each link's own directory
+                symlink("../first/tool", second.join("link")).unwrap();
+                symlink("link", second.join("hop")).unwrap();
+                let path_order = [first.clone(), second.clone()];
+
+                let exact = NameMatcher::new("tool").unwrap();
+                assert!(matches!(exact, NameMatcher::Exact(_)));
+                assert!(exact.matches("tool") && !exact.matches("tools"));
+                let glob = NameMatcher::new("t?o[l]*").unwrap();
+                assert!(matches!(glob, NameMatcher::Glob(_)));
+                assert!(glob.matches("tool") && glob.matches("tools") && !glob.matches("atool"));
+                let regex = NameMatcher::new("/^(hop|link)$/").unwrap();
+                assert!(matches!(regex, NameMatcher::Regex(_)));
+                assert!(regex.matches("hop") && !regex.matches("hops"));
+                assert!(NameMatcher::new("/(/").is_err());
+
+                assert_eq!(symlink_chain(&second.join("hop")), [second.join("link"), second.join("../first/tool")]);
+                assert_eq!(symlink_chain(&first.join("tool")), [] as [PathBuf; 0]);
+
+                assert!(which(&["tool".into(), "/^h/".into(), "l*".into()], &path_order).is_ok());
+                // not executable: never a match, so an error (nonzero exit) like any other miss
+                assert!(which(&["notes.txt".into()], &path_order).is_err());
+                assert!(which(&["tool".into(), "nowhere".into()], &path_order).is_err());
+                assert!(which(&["tool".into()], &[]).is_err());
+        }
+
+        #[test]
+        fn shadows_in_precedence_order() {
+                let found = |file: &str, dir: &str, path_index| FoundPath {
+                        file: file.to_string(),
+                        path: Path::new(dir).join(file),
+                        path_index,
+                        depth: 1,
+                };
+                // as `scan_path` lists them: by `$PATH` index, then by name
+                let executables = vec![
+                        found("solo", "/x/cargo/bin", 0),
+                        found("tool", "/x/cargo/bin", 0),
+                        found("git", "/opt/bin", 2),
+                        found("tool", "/opt/bin", 2),
+                        found("git", "/usr/bin", 3),
+                        found("tool", "/usr/bin", 3),
+                ];
+                let classifier = SourceClassifier {
+                        prefixes: vec![(PathBuf::from("/x/cargo/bin"), Source::Cargo), (PathBuf::from("/usr/bin"), Source::System)],
+                        rustup:   None,
+                };
+
+                let shadows = Shadows::from_executables(executables, &classifier);
+                // lowest index first (the winner), then each shadowed one in precedence order; unshadowed names left out
+                let listed: Vec<_> = shadows.by_name.iter().map(|(name, entries)| (name.as_str(), entries.as_slice())).collect();
+                assert_eq!(listed, [
+                        ("git", &[(2, PathBuf::from("/opt/bin/git"), Source::Other), (3, PathBuf::from("/usr/bin/git"), Source::System)][..]),
+                        ("tool", &[
+                                (0, PathBuf::from("/x/cargo/bin/tool"), Source::Cargo),
+                                (2, PathBuf::from("/opt/bin/tool"), Source::Other),
+                                (3, PathBuf::from("/usr/bin/tool"), Source::System),
+                        ][..]),
+                ]);
+        }
+
+        #[test]
+        fn scan_depth_filtering_and_errors() {
+                let dir = tempfile::tempdir().unwrap();
+                let root = fs::canonicalize(dir.path()).unwrap();
+                let bin = root.join("bin");
+                fs::create_dir_all(bin.join("sub")).unwrap();
+                let file = |path: &Path, mode: u32| {
+                        fs::write(path, "").unwrap();
+                        fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+                };
+                file(&bin.join("run"), 0o755);
+                file(&bin.join("data"), 0o644);
+                file(&bin.join("sub/deep"), 0o755);
+                symlink("run", bin.join("alias")).unwrap();
+                symlink("nowhere", bin.join("dangling")).unwrap();
+                let names = |found: &[FoundPath]| found.iter().map(|f| (f.file.clone(), f.depth)).collect::<Vec<_>>();
+                let owned = |expected: &[(&str, usize)]| expected.iter().map(|&(n, d)| (n.to_string(), d)).collect::<Vec<_>>();
+
+                let mut forbidden_map = HashMap::new();
+                // default: executables (and links to one) directly inside; not data, directories, deeper files or broken links
+                let found = scan_path(std::slice::from_ref(&bin), false, false, &mut forbidden_map);
+                assert_eq!(names(&found), owned(&[("alias", 1), ("run", 1)]));
+                let found = scan_path(std::slice::from_ref(&bin), false, true, &mut forbidden_map);
+                assert_eq!(names(&found), owned(&[("alias", 1), ("dangling", 1), ("run", 1)]));
+                // recursive: everything, the entry itself at depth 0
+                let found = scan_path(std::slice::from_ref(&bin), true, false, &mut forbidden_map);
+                let expected = [("bin", 0), ("alias", 1), ("dangling", 1), ("data", 1), ("run", 1), ("sub", 1), ("deep", 2)];
+                assert_eq!(names(&found), owned(&expected));
+                assert!(forbidden_map.is_empty());
+
+                // only the dangling link is shown as broken
+                let found_paths = FoundPaths {
+                        found_paths:     scan_path(std::slice::from_ref(&bin), false, true, &mut forbidden_map).into_iter().map(|f| (f, None)).collect(),
+                        columns:         vec![Column::Broken],
+                        group_by_source: false,
+                };
+                let shown = found_paths.to_string();
+                let broken: Vec<_> = shown.lines().filter(|line| line.contains("(broken)")).collect();
+                assert_eq!(broken.len(), 1, "{shown}");
+                assert!(broken[0].contains("dangling"));
+
+                // two missing entries and one through a file: three errors, two kinds
+                let path_order = [root.join("missing"), root.join("also-missing"), bin.join("run/below")];
+                assert!(scan_path(&path_order, false, false, &mut forbidden_map).is_empty());
+                assert_eq!(forbidden_map[&io::ErrorKind::NotFound].len(), 2);
+                assert_eq!(forbidden_map[&io::ErrorKind::NotADirectory], [(2, 0, bin.join("run/below"))]);
+                let summary = anstream::adapter::strip_str(&walk_error_summary(&forbidden_map)).to_string();
+                assert_eq!(summary, "3 errors (of 2 kinds) were recorded during directory walk.");
+        }
+
+        #[test]
+        fn records_round_trip() {
+                let found = FoundPath { file: "tab\there".into(), path: "/bin/tab\there".into(), path_index: 3, depth: 1 };
+                let records = [
+                        Record::found(&found, Some(Source::Other)),
+                        Record::raw_path(0, Path::new("/usr/bin")),
+                        Record::error(io::ErrorKind::NotFound, &(2, 0, PathBuf::from("/gone"))),
+                ];
+                // documented fields, as text ("" for missing): kind, file, path, path_index, depth, error_kind
+                let expected = [
+                        ["file", "tab\there", "/bin/tab\there", "3", "1", ""],
+                        ["path", "", "/usr/bin", "0", "", ""],
+                        ["error", "", "/gone", "2", "0", "NotFound"],
+                ];
+                let fields = ["kind", "file", "path", "path_index", "depth", "error_kind"];
+                let from_json = |value: &serde_json::Value| {
+                        fields.map(|field| match &value[field] {
+                                | serde_json::Value::Null => String::new(),
+                                | serde_json::Value::String(s) => s.clone(),
+                                | other => other.to_string(),
+                        })
+                };
+                let written = |format| {
+                        let mut out = Vec::new();
+                        write_records(&mut out, format, &records).unwrap();
+                        String::from_utf8(out).unwrap()
+                };
+
+                let json: Vec<serde_json::Value> = serde_json::from_str(&written(OutputFormat::Json)).unwrap();
+                assert_eq!(json.iter().map(from_json).collect::<Vec<_>>(), expected);
+                let ndjson = written(OutputFormat::Ndjson);
+                let ndjson: Vec<_> = ndjson.lines().map(|line| from_json(&serde_json::from_str(line).unwrap())).collect();
+                assert_eq!(ndjson, expected);
+                for (format, delimiter) in [(OutputFormat::Csv, b','), (OutputFormat::Tsv, b'\t')] {
+                        let text = written(format);
+                        let mut reader = csv::ReaderBuilder::new().delimiter(delimiter).from_reader(text.as_bytes());
+                        let headers = reader.headers().unwrap().clone();
+                        let columns = fields.map(|field| headers.iter().position(|h| h == field).unwrap());
+                        let rows: Vec<_> = reader.records().map(|row| columns.map(|i| row.as_ref().unwrap()[i].to_string())).collect();
+                        assert_eq!(rows, expected, "{format:?}");
+                }
+                // a tab inside a tsv field is quoted, not taken as a separator
+                assert!(written(OutputFormat::Tsv).lines().nth(1).unwrap().starts_with("file\t\"tab\there\"\t"));
+        }
+}